@@ -0,0 +1,445 @@
+//! Alternating finite automata
+//!
+//! Each transition is a positive boolean formula over states, written
+//! like `q2 & (q3 | q4)`, `true` or `false`. Reading a character in a
+//! state, the rest of the input must be accepted from enough of the
+//! states named in the formula to make it true.
+
+use crate::nfa::{show_set, NFA};
+use automata_draw::graphviz::{escape, Edges};
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+// *********************************************************************
+/// # Positive boolean formula over states (1 relative)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Formula {
+    True,
+    False,
+    State(usize),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+}
+
+// *********************************************************************
+/// # Alternating Finite Automata Structure
+#[derive(Debug, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AFA {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Matrix of transitions, rows are states, columns characters in the alphabet
+    pub transitions: Vec<Vec<Formula>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Formula enumeration
+impl Formula {
+    /// Value of the formula when exactly the states in `states` are true
+    pub fn eval(&self, states: &BTreeSet<usize>) -> bool {
+        match self {
+            Formula::True => true,
+            Formula::False => false,
+            Formula::State(q) => states.contains(q),
+            Formula::And(x, y) => x.eval(states) && y.eval(states),
+            Formula::Or(x, y) => x.eval(states) || y.eval(states),
+        }
+    }
+
+    /// States named in the formula
+    pub fn states(&self) -> BTreeSet<usize> {
+        match self {
+            Formula::True | Formula::False => BTreeSet::new(),
+            Formula::State(q) => std::iter::once(*q).collect(),
+            Formula::And(x, y) | Formula::Or(x, y) => {
+                x.states().union(&y.states()).copied().collect()
+            }
+        }
+    }
+
+    /// Minimal sets of states that make the formula true
+    pub fn models(&self) -> Vec<BTreeSet<usize>> {
+        let mut models: Vec<BTreeSet<usize>> = match self {
+            Formula::True => vec![BTreeSet::new()],
+            Formula::False => vec![],
+            Formula::State(q) => vec![std::iter::once(*q).collect()],
+            Formula::Or(x, y) => x.models().into_iter().chain(y.models()).collect(),
+            Formula::And(x, y) => {
+                let right = y.models();
+                x.models()
+                    .iter()
+                    .flat_map(|l| right.iter().map(move |r| l.union(r).copied().collect()))
+                    .collect()
+            }
+        };
+
+        // keep only those without a smaller one inside them
+        models.sort_by_key(|m| m.len());
+        let mut minimal: Vec<BTreeSet<usize>> = Vec::new();
+        for m in models {
+            if !minimal.iter().any(|smaller| smaller.is_subset(&m)) {
+                minimal.push(m);
+            }
+        }
+        minimal
+    }
+}
+
+// *********************************************************************
+/// # Recursive descent parser for formulas
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+// *********************************************************************
+/// Implement the methods of the Parser structure
+impl Parser {
+    /// Split `text` into `(`, `)`, `&`, `|` and words
+    fn new(text: &str) -> Parser {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        }
+        if !word.is_empty() {
+            tokens.push(word);
+        }
+        Parser { tokens, pos: 0 }
+    }
+
+    /// Next token, if it is `token`
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.tokens.get(self.pos).map(|t| t == token) == Some(true);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// or := and ('|' and)*
+    fn or(&mut self) -> Result<Formula, String> {
+        let mut formula = self.and()?;
+        while self.eat("|") {
+            formula = Formula::Or(Box::new(formula), Box::new(self.and()?));
+        }
+        Ok(formula)
+    }
+
+    /// and := atom ('&' atom)*
+    fn and(&mut self) -> Result<Formula, String> {
+        let mut formula = self.atom()?;
+        while self.eat("&") {
+            formula = Formula::And(Box::new(formula), Box::new(self.atom()?));
+        }
+        Ok(formula)
+    }
+
+    /// atom := 'true' | 'false' | qN | '(' or ')'
+    fn atom(&mut self) -> Result<Formula, String> {
+        if self.eat("(") {
+            let formula = self.or()?;
+            if !self.eat(")") {
+                return Err("missing )".to_string());
+            }
+            return Ok(formula);
+        }
+        let token = match self.tokens.get(self.pos) {
+            Some(token) => token.clone(),
+            None => return Err("unexpected end".to_string()),
+        };
+        self.pos += 1;
+        match token.as_str() {
+            "true" => Ok(Formula::True),
+            "false" => Ok(Formula::False),
+            _ => match token.strip_prefix('q').map(|n| n.parse()) {
+                Some(Ok(q)) => Ok(Formula::State(q)),
+                _ => Err(format!("unexpected {}", token)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Formula {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Formula, String> {
+        let mut parser = Parser::new(&text);
+        let formula = parser.or();
+        match formula {
+            Ok(formula) if parser.pos == parser.tokens.len() => Ok(formula),
+            Ok(_) => Err(format!(
+                "Invalid formula({}): unexpected {}",
+                text, parser.tokens[parser.pos]
+            )),
+            Err(e) => Err(format!("Invalid formula({}): {}", text, e)),
+        }
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the AFA structure
+impl AFA {
+    /// Create and return an AFA on the heap
+    pub fn new_from_file(filename: &str) -> Box<AFA> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the AFA
+    pub fn validate(&self) -> Result<(), String> {
+        for (rnum, row) in self.transitions.iter().enumerate() {
+            if row.len() != self.alphabet.len() {
+                return Err(format!(
+                    "Wrong number of columns({}) in row {}, should be {}",
+                    row.len(),
+                    rnum + 1,
+                    self.alphabet.len()
+                ));
+            }
+            for (cnum, formula) in row.iter().enumerate() {
+                for state in formula.states() {
+                    if state == 0 || state > self.transitions.len() {
+                        return Err(format!(
+                            "Invalid transition state({}) in row {}, column {}",
+                            state,
+                            rnum + 1,
+                            cnum + 1
+                        ));
+                    }
+                }
+            }
+        }
+
+        // The start and accept states must be valid
+        if self.start == 0 || self.start > self.transitions.len() {
+            return Err(format!("Start state({}), is not valid", self.start));
+        }
+        for acc_state in self.accept.iter() {
+            if *acc_state == 0 || *acc_state > self.transitions.len() {
+                return Err(format!("Accept state({}), is not valid", acc_state));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Is `input` accepted, printing the states that accept each suffix
+    /// when `print` is set
+    ///
+    /// Works backwards from the accept states, which accept the empty
+    /// suffix: a state accepts `c` followed by a suffix when its formula
+    /// for `c` is true of the states accepting that suffix.
+    pub fn accepts(&self, input: &str, print: bool) -> bool {
+        let chars: Vec<char> = input.chars().collect();
+        let mut states: BTreeSet<usize> = self.accept.iter().copied().collect();
+        if print {
+            println!("States accepting each suffix:");
+            println!("\tε\t{}", show_set(&states));
+        }
+        for (n, c) in chars.iter().enumerate().rev() {
+            let i = self.alphabet.iter().position(|a| a == c).unwrap();
+            states = (1..=self.transitions.len())
+                .filter(|q| self.transitions[q - 1][i].eval(&states))
+                .collect();
+            if print {
+                let suffix: String = chars[n..].iter().collect();
+                println!("\t{}\t{}", suffix, show_set(&states));
+            }
+        }
+        states.contains(&self.start)
+    }
+
+    /// Equivalent NFA
+    ///
+    /// Its states are the sets of AFA states that must all accept the rest
+    /// of the input, kept in `sets`. Reading a character it picks, for each state in the
+    /// set, a minimal set of states making that state's formula true.
+    pub fn to_nfa(&self) -> NFA {
+        let mut sets: Vec<BTreeSet<usize>> = vec![std::iter::once(self.start).collect()];
+        let mut transitions = Vec::new();
+        let mut n = 0;
+        while n < sets.len() {
+            let row = (0..self.alphabet.len())
+                .map(|i| {
+                    // one choice of model for each state, combined
+                    let mut choices = vec![BTreeSet::new()];
+                    for q in &sets[n] {
+                        let models = self.transitions[q - 1][i].models();
+                        choices = choices
+                            .iter()
+                            .flat_map(|c: &BTreeSet<usize>| {
+                                models.iter().map(move |m| c.union(m).copied().collect())
+                            })
+                            .collect();
+                    }
+                    let mut targets: Vec<usize> = choices
+                        .into_iter()
+                        .map(|set| match sets.iter().position(|s| *s == set) {
+                            Some(p) => p + 1,
+                            None => {
+                                sets.push(set);
+                                sets.len()
+                            }
+                        })
+                        .collect();
+                    targets.sort_unstable();
+                    targets.dedup();
+                    targets
+                })
+                .collect();
+            transitions.push(row);
+            n += 1;
+        }
+
+        NFA {
+            alphabet: self.alphabet.clone(),
+            start: 1,
+            accept: (1..=sets.len())
+                .filter(|s| sets[s - 1].iter().all(|q| self.accept.contains(q)))
+                .collect(),
+            transitions,
+            sets,
+        }
+    }
+
+    /// Write the graph to stdout
+    ///
+    /// Each transition goes to a box showing its formula, with dashed
+    /// edges on to the states the formula names.
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape=doublecircle]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+
+        let mut edges = Edges::new();
+        let mut dashed = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                let node = format!("f{}_{}", n + 1, i + 1);
+                println!(
+                    "\t{} [shape=box, label=\"{}\"];",
+                    node,
+                    escape(&row[i].to_string())
+                );
+                edges.add(&format!("q{}", n + 1), &node, &ch.to_string());
+                for state in row[i].states() {
+                    dashed.add(&node, &format!("q{}", state), "");
+                }
+            }
+        }
+        edges.write();
+        dashed.write_with(", ", None, "style=dashed");
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram, each formula
+    /// a state of its own with an edge to every state in it
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.accept = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                let node = format!("f{}_{}", n + 1, i + 1);
+                diagram.labels.push((node.clone(), row[i].to_string()));
+                edges.add(&format!("q{}", n + 1), &node, &ch.to_string());
+                for state in row[i].states() {
+                    edges.add(&node, &format!("q{}", state), "");
+                }
+            }
+        }
+        diagram.write(&edges);
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Formula::True => write!(f, "true"),
+            Formula::False => write!(f, "false"),
+            Formula::State(q) => write!(f, "q{}", q),
+            Formula::Or(x, y) => write!(f, "{} | {}", x, y),
+            Formula::And(x, y) => {
+                // | binds looser than &, so bracket it
+                for (n, side) in [x, y].iter().enumerate() {
+                    if n == 1 {
+                        write!(f, " & ")?;
+                    }
+                    match side.as_ref() {
+                        Formula::Or(..) => write!(f, "({})", side)?,
+                        _ => write!(f, "{}", side)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_afa_conversions() {
+    // contains both aa and bb
+    let afa = AFA::new_from_file("afa.yaml");
+    assert_eq!(afa.validate(), Ok(()));
+    let nfa = afa.to_nfa();
+    let (dfa, _) = nfa.to_dfa();
+    assert_eq!(dfa.validate(), Ok(()));
+    let graph = crate::StateGraph::new_from_dfa(&dfa);
+
+    // every string of up to 6 characters
+    let mut strings = vec![String::new()];
+    for _ in 0..6 {
+        let longer: Vec<String> = strings
+            .iter()
+            .filter(|s| s.len() == strings.last().unwrap().len())
+            .flat_map(|s| vec![format!("{}a", s), format!("{}b", s)])
+            .collect();
+        strings.extend(longer);
+    }
+    for input in strings {
+        let expected = input.contains("aa") && input.contains("bb");
+        assert_eq!(afa.accepts(&input, false), expected, "{}", input);
+        assert_eq!(nfa.accepts(&input), expected, "{}", input);
+        assert_eq!(
+            graph.check_string(Vec::new(), input.clone()),
+            expected,
+            "{}",
+            input
+        );
+    }
+
+    let formula = Formula::try_from("q1 & (q2 | true) | false".to_string()).unwrap();
+    assert_eq!(formula.to_string(), "q1 & (q2 | true) | false");
+    assert_eq!(formula.models(), vec![std::iter::once(1).collect()]);
+    assert!(Formula::try_from("q1 & | q2".to_string()).is_err());
+    assert!(Formula::try_from("(q1".to_string()).is_err());
+}
@@ -0,0 +1,271 @@
+//! Deterministic Büchi automata
+//!
+//! The file is the DFA transition table, with `accept` read as the
+//! recurring states: an infinite word is accepted when its run passes
+//! through a recurring state infinitely often. Only ultimately periodic
+//! words `u(v)^ω` can be written down, so those are the ones that are
+//! checked.
+
+use crate::transducer::validate_table;
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+
+// *********************************************************************
+/// # Büchi Automata Structure
+#[derive(Debug, Deserialize)]
+pub struct Buchi {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of recurring states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Matrix of transitions, rows are states, columns characters in the alphabet
+    pub transitions: Vec<Vec<usize>>,
+}
+
+// *********************************************************************
+/// # Ultimately periodic word `stem(cycle)^ω`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lasso {
+    /// Word read once
+    pub stem: String,
+
+    /// Word read forever after the stem, never empty
+    pub cycle: String,
+}
+
+// *********************************************************************
+/// Implement the methods of the Buchi structure
+impl Buchi {
+    /// Create and return a Büchi automaton on the heap
+    pub fn new_from_file(filename: &str) -> Box<Buchi> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the Büchi automaton
+    pub fn validate(&self) -> Result<(), String> {
+        validate_table(&self.alphabet, self.start, &self.transitions)?;
+        for acc_state in self.accept.iter() {
+            if *acc_state == 0 || *acc_state > self.transitions.len() {
+                return Err(format!("Recurring state({}), is not valid", acc_state));
+            }
+        }
+        Ok(())
+    }
+
+    /// State entered from `state` reading `c`
+    fn delta(&self, state: usize, c: char) -> usize {
+        let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+        self.transitions[state - 1][i]
+    }
+
+    /// Is `word` accepted, printing the run when `print` is set
+    ///
+    /// After the stem the run reads the cycle from state to state until it
+    /// starts a cycle from a state it started one from before. From then
+    /// on it repeats those same blocks forever, so the word is accepted
+    /// when one of them passes through a recurring state.
+    pub fn accepts(&self, word: &Lasso, print: bool) -> bool {
+        let mut state = self.start;
+        if print {
+            println!("Transition steps:");
+        }
+        for c in word.stem.chars() {
+            let next = self.delta(state, c);
+            if print {
+                println!("d(q{}, {}) -> q{}", state, c, next);
+            }
+            state = next;
+        }
+
+        // where each block of the cycle started, and whether it recurred
+        let mut blocks: Vec<(usize, bool)> = Vec::new();
+        loop {
+            if let Some(first) = blocks.iter().position(|(q, _)| *q == state) {
+                if print {
+                    println!("Back at q{}, the run repeats from here", state);
+                }
+                return blocks[first..].iter().any(|(_, recurring)| *recurring);
+            }
+            let from = state;
+            let mut recurring = false;
+            if print {
+                println!("Cycle from q{}:", from);
+            }
+            for c in word.cycle.chars() {
+                let next = self.delta(state, c);
+                recurring |= self.accept.contains(&next);
+                if print {
+                    let mark = if self.accept.contains(&next) {
+                        " (recurring)"
+                    } else {
+                        ""
+                    };
+                    println!("\td(q{}, {}) -> q{}{}", state, c, next, mark);
+                }
+                state = next;
+            }
+            blocks.push((from, recurring));
+        }
+    }
+
+    /// A word accepted by the automaton, or None when its language is empty
+    ///
+    /// Uses nested depth first search: when the outer search finishes
+    /// with a recurring state, an inner search looks for a cycle back to
+    /// it. The inner search shares its visited set across seeds, which is
+    /// safe because seeds are tried in the order the outer search finishes
+    /// them.
+    pub fn find_lasso(&self) -> Option<Lasso> {
+        let mut search = NestedSearch {
+            buchi: self,
+            outer: HashSet::new(),
+            inner: HashSet::new(),
+            stem: String::new(),
+            cycle: String::new(),
+        };
+        if search.outer_dfs(self.start) {
+            Some(Lasso {
+                stem: search.stem,
+                cycle: search.cycle,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Write the graph to stdout, recurring states shaded double circles
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!(
+                "\tnode [shape=doublecircle, style=filled, fillcolor=lightgrey]; q{};",
+                state
+            );
+        }
+        println!("\tnode [shape=circle, style=solid];");
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram, recurring
+    /// states shaded
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.shaded = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every character
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                edges.add(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", row[i]),
+                    &ch.to_string(),
+                );
+            }
+        }
+        edges
+    }
+}
+
+// *********************************************************************
+/// # State of a nested depth first search
+struct NestedSearch<'a> {
+    buchi: &'a Buchi,
+
+    /// States reached by the outer search
+    outer: HashSet<usize>,
+
+    /// States reached by any inner search
+    inner: HashSet<usize>,
+
+    /// Word read along the outer search path
+    stem: String,
+
+    /// Word read along the inner search path
+    cycle: String,
+}
+
+// *********************************************************************
+/// Implement the methods of the NestedSearch structure
+impl NestedSearch<'_> {
+    /// Search from `state`, true once a lasso has been found
+    fn outer_dfs(&mut self, state: usize) -> bool {
+        self.outer.insert(state);
+        for (i, c) in self.buchi.alphabet.iter().enumerate() {
+            let next = self.buchi.transitions[state - 1][i];
+            if !self.outer.contains(&next) {
+                self.stem.push(*c);
+                if self.outer_dfs(next) {
+                    return true;
+                }
+                self.stem.pop();
+            }
+        }
+        self.buchi.accept.contains(&state) && self.inner_dfs(state, state)
+    }
+
+    /// Search from `state` for a way back to `seed`
+    fn inner_dfs(&mut self, state: usize, seed: usize) -> bool {
+        for (i, c) in self.buchi.alphabet.iter().enumerate() {
+            let next = self.buchi.transitions[state - 1][i];
+            self.cycle.push(*c);
+            if next == seed || (self.inner.insert(next) && self.inner_dfs(next, seed)) {
+                return true;
+            }
+            self.cycle.pop();
+        }
+        false
+    }
+}
+
+impl fmt::Display for Lasso {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({})^ω", self.stem, self.cycle)
+    }
+}
+
+// Test Functions
+#[test]
+fn test_buchi_lassos() {
+    // infinitely many a's
+    let buchi = Buchi::new_from_file("buchi.yaml");
+    assert_eq!(buchi.validate(), Ok(()));
+    let word = |stem: &str, cycle: &str| Lasso {
+        stem: stem.to_string(),
+        cycle: cycle.to_string(),
+    };
+    assert!(buchi.accepts(&word("", "a"), false));
+    assert!(buchi.accepts(&word("bbb", "ab"), false));
+    assert!(!buchi.accepts(&word("aaa", "b"), false));
+    assert!(!buchi.accepts(&word("", "bb"), false));
+
+    let lasso = buchi.find_lasso().unwrap();
+    assert!(buchi.accepts(&lasso, false));
+    assert_eq!(lasso.to_string(), "a(a)^ω");
+
+    // the recurring state is reachable but cannot be left and come back
+    let once: Buchi = serde_yaml::from_str(
+        "alphabet: ['a', 'b']\nstart: 1\naccept: [2]\ntransitions: [[1, 2], [3, 3], [3, 3]]",
+    )
+    .unwrap();
+    assert_eq!(once.validate(), Ok(()));
+    assert_eq!(once.find_lasso(), None);
+}
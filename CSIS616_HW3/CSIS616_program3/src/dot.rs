@@ -0,0 +1,703 @@
+//! Reading automata back from Graphviz DOT
+//!
+//! Covers the subset the writers emit, so that a .gv file written by this
+//! program, or edited by hand afterwards, can be run again: a `digraph`
+//! of attribute statements such as `rankdir=LR;`, `node [shape=...]`
+//! statements setting the shape of the nodes that follow, node
+//! statements, and edges with an optional `label`. A node drawn as a
+//! point is not a state; its edge marks the start state, as in
+//! `start -> q1`. Accept states are the ones drawn as double circles.
+//!
+//! Comments, quoted names and chains like `q1 -> q2 -> q3` are read too.
+//! Subgraphs, HTML labels and undirected graphs are not.
+//!
+//! A digraph can be read as a DFA or, with ε-moves, more than one start
+//! state and more than one transition on a character, as an NFA.
+
+use crate::nfa::NFA;
+use crate::DFA;
+use std::collections::BTreeSet;
+
+// *********************************************************************
+/// # A piece of DOT text
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A name, number or quoted string, quotes removed
+    Id(String),
+
+    /// One of `{ } [ ] ; , =` or `->`
+    Symbol(&'static str),
+}
+
+// *********************************************************************
+/// # The states and edges of a DOT digraph
+#[derive(Debug, Default)]
+pub struct Digraph {
+    /// Name of each state, in the order they first appear
+    pub states: Vec<String>,
+
+    /// Names of the states the start marker points at
+    pub start: Vec<String>,
+
+    /// Names of the accept states
+    pub accept: Vec<String>,
+
+    /// Every edge between states with its label, escapes decoded
+    pub edges: Vec<(String, String, String)>,
+
+    /// Shape of every node, states and start markers
+    shapes: Vec<(String, String)>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Digraph structure
+impl Digraph {
+    /// Create and return a digraph read from a DOT file on the heap
+    pub fn new_from_file(filename: &str) -> Box<Digraph> {
+        let text = std::fs::read_to_string(filename).expect("Unable to open input");
+        Box::new(Digraph::parse(&text).expect("Unable to parse dot"))
+    }
+
+    /// Read the digraph in `text`
+    pub fn parse(text: &str) -> Result<Digraph, String> {
+        let tokens = tokenize(text)?;
+        let mut graph = Digraph::default();
+        let mut shape = "ellipse".to_string();
+        let mut markers = Vec::new();
+        let mut edges = Vec::new();
+
+        // digraph [name] {
+        let mut i = 0;
+        if tokens.get(i).map(|t| &t.0) == Some(&Token::Id("strict".to_string())) {
+            i += 1;
+        }
+        match tokens.get(i) {
+            Some((Token::Id(kind), _)) if kind.eq_ignore_ascii_case("digraph") => i += 1,
+            Some((Token::Id(kind), line)) if kind.eq_ignore_ascii_case("graph") => {
+                return Err(format!("Line {}: undirected graphs are not automata", line))
+            }
+            _ => return Err("The file does not start with digraph".to_string()),
+        }
+        if let Some((Token::Id(_), _)) = tokens.get(i) {
+            i += 1;
+        }
+        expect(&tokens, &mut i, "{")?;
+
+        loop {
+            let (token, line) = match tokens.get(i) {
+                Some((token, line)) => (token.clone(), *line),
+                None => return Err("The digraph has no closing }".to_string()),
+            };
+            i += 1;
+            let name = match token {
+                Token::Symbol("}") => break,
+                Token::Symbol(";") => continue,
+                Token::Symbol(s) => return Err(format!("Line {}: unexpected {}", line, s)),
+                Token::Id(name) => name,
+            };
+
+            // defaults for the nodes, edges or graph that follow
+            if ["node", "edge", "graph"].contains(&name.as_str())
+                && tokens.get(i).map(|t| &t.0) == Some(&Token::Symbol("["))
+            {
+                let attributes = attribute_list(&tokens, &mut i)?;
+                if name == "node" {
+                    if let Some(s) = find(&attributes, "shape") {
+                        shape = s;
+                    }
+                }
+                continue;
+            }
+            if name == "subgraph" {
+                return Err(format!("Line {}: subgraphs are not supported", line));
+            }
+
+            // a graph attribute such as rankdir=LR
+            if tokens.get(i).map(|t| &t.0) == Some(&Token::Symbol("=")) {
+                i += 1;
+                match tokens.get(i) {
+                    Some((Token::Id(_), _)) => i += 1,
+                    _ => return Err(format!("Line {}: {} has no value", line, name)),
+                }
+                continue;
+            }
+
+            // a node, or a chain of edges
+            let mut chain = vec![name];
+            while tokens.get(i).map(|t| &t.0) == Some(&Token::Symbol("->")) {
+                i += 1;
+                match tokens.get(i) {
+                    Some((Token::Id(next), _)) => chain.push(next.clone()),
+                    _ => return Err(format!("Line {}: -> is not followed by a node", line)),
+                }
+                i += 1;
+            }
+            let attributes = attribute_list(&tokens, &mut i)?;
+            for node in &chain {
+                if !graph.shapes.iter().any(|(n, _)| n == node) {
+                    graph.shapes.push((node.clone(), shape.clone()));
+                }
+            }
+            if chain.len() == 1 {
+                if let Some(s) = find(&attributes, "shape") {
+                    let entry = graph.shapes.iter_mut().find(|(n, _)| *n == chain[0]);
+                    entry.unwrap().1 = s;
+                }
+            }
+            for pair in chain.windows(2) {
+                let label = find(&attributes, "label").map(|l| decode(&l));
+                edges.push((pair[0].clone(), pair[1].clone(), label, line));
+            }
+        }
+        if let Some((_, line)) = tokens.get(i) {
+            return Err(format!("Line {}: text after the closing }}", line));
+        }
+
+        // start markers are drawn as points, the other nodes are states
+        for (node, shape) in &graph.shapes {
+            if shape == "point" {
+                markers.push(node.clone());
+            }
+        }
+        for (from, to, label, line) in edges {
+            if markers.contains(&to) {
+                return Err(format!(
+                    "Line {}: {} -> {} enters a start marker",
+                    line, from, to
+                ));
+            }
+            if markers.contains(&from) {
+                if !graph.start.contains(&to) {
+                    graph.start.push(to.clone());
+                }
+            } else {
+                let label = label
+                    .ok_or_else(|| format!("Line {}: {} -> {} has no label", line, from, to))?;
+                graph.edges.push((from.clone(), to.clone(), label));
+            }
+            for state in [from, to] {
+                if !markers.contains(&state) && !graph.states.contains(&state) {
+                    graph.states.push(state);
+                }
+            }
+        }
+        for (node, shape) in &graph.shapes {
+            if !markers.contains(node) && !graph.states.contains(node) {
+                graph.states.push(node.clone());
+            }
+            if shape == "doublecircle" {
+                graph.accept.push(node.clone());
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Validate that the digraph is a DFA
+    ///
+    /// There must be one start state, no ε-moves, and every state must
+    /// have exactly one transition on each character labelling any edge.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.start.len() {
+            0 => return Err("There is no start state, no point -> state edge".to_string()),
+            1 => {}
+            _ => {
+                return Err(format!(
+                    "More than one start state({})",
+                    self.start.join(", ")
+                ))
+            }
+        }
+
+        let alphabet = self.alphabet()?;
+        if let Some((from, to, _)) = self.moves().find(|(_, _, c)| *c == 'ε') {
+            return Err(format!("{} -> {} is an ε-move, a DFA has none", from, to));
+        }
+        for state in &self.states {
+            for c in &alphabet {
+                let targets: Vec<&String> = self
+                    .moves()
+                    .filter(|(from, _, ch)| *from == state && ch == c)
+                    .map(|(_, to, _)| to)
+                    .collect();
+                match targets.len() {
+                    0 => return Err(format!("State {} has no transition on {}", state, c)),
+                    1 => {}
+                    _ => {
+                        let names: Vec<&str> = targets.iter().map(|t| t.as_str()).collect();
+                        return Err(format!(
+                            "State {} has more than one transition on {} (to {})",
+                            state,
+                            c,
+                            names.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate that the digraph is an NFA
+    ///
+    /// There must be a start state, and every label must be ε or single
+    /// characters.
+    pub fn validate_nfa(&self) -> Result<(), String> {
+        if self.start.is_empty() {
+            return Err("There is no start state, no point -> state edge".to_string());
+        }
+        self.alphabet().map(|_| ())
+    }
+
+    /// Characters on the edges other than ε, in order, each label being
+    /// one character or several separated by commas
+    fn alphabet(&self) -> Result<Vec<char>, String> {
+        let mut alphabet = Vec::new();
+        for (from, to, label) in &self.edges {
+            for part in label.split(", ") {
+                let mut chars = part.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('ε'), None) => {}
+                    (Some(c), None) => {
+                        if !alphabet.contains(&c) {
+                            alphabet.push(c);
+                        }
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Label({}) of {} -> {} is not a single character",
+                            part, from, to
+                        ))
+                    }
+                }
+            }
+        }
+        alphabet.sort_unstable();
+        Ok(alphabet)
+    }
+
+    /// Every transition, one for each character of each edge
+    fn moves(&self) -> impl Iterator<Item = (&String, &String, char)> {
+        self.edges.iter().flat_map(|(from, to, label)| {
+            label
+                .split(", ")
+                .filter_map(move |part| part.chars().next().map(|c| (from, to, c)))
+        })
+    }
+
+    /// Number (1 relative) of each state in the DFA
+    ///
+    /// States named q1 to qn keep their numbers, otherwise they are
+    /// numbered in the order they first appear.
+    pub fn numbers(&self) -> Vec<usize> {
+        let named: Vec<Option<usize>> = self
+            .states
+            .iter()
+            .map(|s| s.strip_prefix('q').and_then(|n| n.parse().ok()))
+            .collect();
+        let n = self.states.len();
+        let mut sorted: Vec<usize> = named.iter().flatten().copied().collect();
+        sorted.sort_unstable();
+        if sorted == (1..=n).collect::<Vec<usize>>() {
+            named.into_iter().flatten().collect()
+        } else {
+            (1..=n).collect()
+        }
+    }
+
+    /// The DFA drawn, once the digraph has been validated
+    pub fn to_dfa(&self) -> DFA {
+        let alphabet = self.alphabet().expect("Validation Failure:");
+        let numbers = self.numbers();
+        let number = |name: &String| numbers[self.states.iter().position(|s| s == name).unwrap()];
+
+        let mut transitions = vec![vec![0; alphabet.len()]; self.states.len()];
+        for (from, to, c) in self.moves() {
+            let column = alphabet.iter().position(|a| *a == c).unwrap();
+            transitions[number(from) - 1][column] = number(to);
+        }
+
+        DFA {
+            alphabet,
+            start: number(&self.start[0]),
+            accept: self.accept.iter().map(number).collect(),
+            transitions,
+        }
+    }
+
+    /// The NFA drawn, once the digraph has been validated by `validate_nfa`
+    ///
+    /// ε-moves are removed: a character read in a state goes wherever it
+    /// goes from the state's ε-closure, then on to the ε-closures of those
+    /// states, and a state accepts when its ε-closure holds an accept
+    /// state. Several start states are joined into a new one numbered
+    /// after the others, whose set in `sets` is the start states.
+    pub fn to_nfa(&self) -> NFA {
+        let alphabet = self.alphabet().expect("Validation Failure:");
+        let numbers = self.numbers();
+        let number = |name: &String| numbers[self.states.iter().position(|s| s == name).unwrap()];
+        let n = self.states.len();
+
+        // ε-closure of each state, grown until no ε-move adds to it
+        let epsilon: Vec<(usize, usize)> = self
+            .moves()
+            .filter(|(_, _, c)| *c == 'ε')
+            .map(|(from, to, _)| (number(from), number(to)))
+            .collect();
+        let mut closures: Vec<BTreeSet<usize>> =
+            (1..=n).map(|q| std::iter::once(q).collect()).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (from, to) in &epsilon {
+                for q in closures[to - 1].clone() {
+                    changed |= closures[from - 1].insert(q);
+                }
+            }
+        }
+
+        // the states each NFA state moves from, and the new start state
+        let mut sets: Vec<BTreeSet<usize>> =
+            (1..=n).map(|q| std::iter::once(q).collect()).collect();
+        let mut from = closures.clone();
+        let start = if self.start.len() == 1 {
+            number(&self.start[0])
+        } else {
+            sets.push(self.start.iter().map(number).collect());
+            from.push(
+                self.start
+                    .iter()
+                    .flat_map(|s| closures[number(s) - 1].clone())
+                    .collect(),
+            );
+            n + 1
+        };
+
+        let moves: Vec<(usize, usize, char)> = self
+            .moves()
+            .filter(|(_, _, c)| *c != 'ε')
+            .map(|(p, q, c)| (number(p), number(q), c))
+            .collect();
+        let transitions = from
+            .iter()
+            .map(|states| {
+                alphabet
+                    .iter()
+                    .map(|a| {
+                        let next: BTreeSet<usize> = moves
+                            .iter()
+                            .filter(|(p, _, c)| c == a && states.contains(p))
+                            .flat_map(|(_, q, _)| closures[q - 1].iter().copied())
+                            .collect();
+                        next.into_iter().collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let accept: Vec<usize> = self.accept.iter().map(number).collect();
+
+        NFA {
+            alphabet,
+            start,
+            accept: (1..=from.len())
+                .filter(|q| from[q - 1].iter().any(|p| accept.contains(p)))
+                .collect(),
+            transitions,
+            sets,
+        }
+    }
+}
+
+// *********************************************************************
+/// Split DOT text into tokens, each with the line it is on
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+
+            // # lines are preprocessor output, // and /* */ comments
+            c if (c == '#' && line_start) || (c == '/' && chars.peek() == Some(&'/')) => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        }
+                        None => return Err(format!("Line {}: comment is not closed", line)),
+                    }
+                }
+            }
+
+            '{' => tokens.push((Token::Symbol("{"), line)),
+            '}' => tokens.push((Token::Symbol("}"), line)),
+            '[' => tokens.push((Token::Symbol("["), line)),
+            ']' => tokens.push((Token::Symbol("]"), line)),
+            ';' => tokens.push((Token::Symbol(";"), line)),
+            ',' => tokens.push((Token::Symbol(","), line)),
+            '=' => tokens.push((Token::Symbol("="), line)),
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((Token::Symbol("->"), line));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                return Err(format!("Line {}: -- edges are undirected", line));
+            }
+            '<' => return Err(format!("Line {}: HTML labels are not supported", line)),
+
+            // quoted strings keep their escapes, except for \"
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            s.push('"');
+                        }
+                        Some('\\') if chars.peek() == Some(&'\n') => {
+                            chars.next();
+                            line += 1;
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            s.push(c);
+                        }
+                        None => return Err(format!("Line {}: string is not closed", line)),
+                    }
+                }
+                tokens.push((Token::Id(s), line));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Id(s), line));
+            }
+            c => return Err(format!("Line {}: unexpected {}", line, c)),
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+// *********************************************************************
+/// Skip the symbol `symbol` at token `i`, an error if it is not there
+fn expect(tokens: &[(Token, usize)], i: &mut usize, symbol: &str) -> Result<(), String> {
+    match tokens.get(*i) {
+        Some((Token::Symbol(s), _)) if *s == symbol => {
+            *i += 1;
+            Ok(())
+        }
+        Some((_, line)) => Err(format!("Line {}: expected {}", line, symbol)),
+        None => Err(format!("Expected {} at the end of the file", symbol)),
+    }
+}
+
+// *********************************************************************
+/// Read the attribute lists starting at token `i`, if there are any, as
+/// name and value pairs
+fn attribute_list(
+    tokens: &[(Token, usize)],
+    i: &mut usize,
+) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    while tokens.get(*i).map(|t| &t.0) == Some(&Token::Symbol("[")) {
+        *i += 1;
+        loop {
+            match tokens.get(*i) {
+                Some((Token::Symbol("]"), _)) => {
+                    *i += 1;
+                    break;
+                }
+                Some((Token::Symbol(";"), _)) | Some((Token::Symbol(","), _)) => *i += 1,
+                Some((Token::Id(name), _)) => {
+                    *i += 1;
+                    expect(tokens, i, "=")?;
+                    match tokens.get(*i) {
+                        Some((Token::Id(value), _)) => {
+                            attributes.push((name.clone(), value.clone()));
+                            *i += 1;
+                        }
+                        Some((_, line)) => {
+                            return Err(format!("Line {}: {} has no value", line, name))
+                        }
+                        None => return Err("The attribute list has no closing ]".to_string()),
+                    }
+                }
+                Some((Token::Symbol(s), line)) => {
+                    return Err(format!("Line {}: unexpected {}", line, s))
+                }
+                None => return Err("The attribute list has no closing ]".to_string()),
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+// *********************************************************************
+/// Value of the last attribute called `name`
+fn find(attributes: &[(String, String)], name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .rev()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.clone())
+}
+
+// *********************************************************************
+/// Decode a label written by `graphviz::escape`
+///
+/// `\\` is a backslash and `\n` a newline; `&amp;`, `&lt;`, `&gt;`,
+/// `&quot;` and `&#N;` entities are the characters they stand for.
+fn decode(label: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = label;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix('\\') {
+            match after.chars().next() {
+                Some('n') | Some('l') | Some('r') => decoded.push('\n'),
+                Some(c) => decoded.push(c),
+                None => decoded.push('\\'),
+            }
+            rest = &after[after.chars().next().map_or(0, |c| c.len_utf8())..];
+            continue;
+        }
+        if c == '&' {
+            if let Some(end) = rest.find(';') {
+                let entity = &rest[1..end];
+                let named = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    _ => entity
+                        .strip_prefix('#')
+                        .and_then(|n| n.parse().ok())
+                        .and_then(std::char::from_u32),
+                };
+                if let Some(ch) = named {
+                    decoded.push(ch);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        decoded.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    decoded
+}
+
+// Test Functions
+#[test]
+fn test_dot_import() {
+    // the file written for sample.yaml reads back as the same DFA
+    let digraph = Digraph::new_from_file("sample.gv");
+    assert_eq!(digraph.validate(), Ok(()));
+    let dfa = digraph.to_dfa();
+    let sample = DFA::new_from_file("sample.yaml");
+    assert_eq!(dfa.alphabet, sample.alphabet);
+    assert_eq!(dfa.start, sample.start);
+    assert_eq!(dfa.accept, sample.accept);
+    assert_eq!(dfa.transitions, sample.transitions);
+
+    // drawn by hand, with names, comments, chains and merged labels
+    let text = "// even number of a
+        digraph G {
+            init [shape=point]
+            node [shape = circle]; odd
+            \"even\" [shape=doublecircle, label=\"even\"]
+            init -> even; /* start */
+            even -> odd -> even [label=\"a\"]
+            even -> even [label=\"b, &#949;\"]
+        }";
+    let digraph = Digraph::parse(text).unwrap();
+    assert_eq!(digraph.states, vec!["even", "odd"]);
+    assert_eq!(digraph.start, vec!["even"]);
+    assert_eq!(digraph.accept, vec!["even"]);
+    assert_eq!(
+        digraph.validate(),
+        Err("even -> even is an ε-move, a DFA has none".to_string())
+    );
+    let digraph = Digraph::parse(&text.replace(", &#949;", "")).unwrap();
+    assert_eq!(
+        digraph.validate(),
+        Err("State odd has no transition on b".to_string())
+    );
+    let digraph =
+        Digraph::parse(&text.replace("b, &#949;", "b\"];odd -> odd [label=\"b, a")).unwrap();
+    assert_eq!(
+        digraph.validate(),
+        Err("State odd has more than one transition on a (to even, odd)".to_string())
+    );
+
+    assert_eq!(
+        Digraph::parse("digraph { a -> b [label=\"x\" }").unwrap_err(),
+        "Line 1: unexpected }"
+    );
+    assert_eq!(decode("x\\\\&amp;&#949;\\n"), "x\\&ε\n");
+}
+
+#[test]
+fn test_nfa_import() {
+    // two start states, an ε-move and a choice on a in q1
+    let digraph = Digraph::new_from_file("nfa.gv");
+    assert_eq!(
+        digraph.validate(),
+        Err("More than one start state(q1, q5)".to_string())
+    );
+    assert_eq!(digraph.validate_nfa(), Ok(()));
+    let nfa = digraph.to_nfa();
+    assert_eq!(nfa.alphabet, vec!['a', 'b']);
+    assert_eq!(nfa.start, 6);
+    assert_eq!(nfa.sets[5], [1, 5].iter().copied().collect());
+    assert_eq!(nfa.accept, vec![3, 4, 5, 6]);
+    assert_eq!(nfa.transitions[0][0], vec![1, 2]);
+    assert_eq!(nfa.transitions[4][0], vec![4]);
+
+    // the subset construction agrees on every string up to length 4
+    let (dfa, _) = nfa.to_dfa();
+    let mut strings = vec![String::new()];
+    for length in 0..4 {
+        let longer: Vec<String> = strings
+            .iter()
+            .filter(|s| s.len() == length)
+            .flat_map(|s| ["a", "b"].iter().map(move |c| format!("{}{}", s, c)))
+            .collect();
+        strings.extend(longer);
+    }
+    for s in &strings {
+        let accept = s.chars().all(|c| c == 'a') || s.ends_with("ab");
+        assert_eq!(nfa.accepts(s), accept, "{}", s);
+        let state = s.chars().fold(dfa.start, |q, c| {
+            let column = dfa.alphabet.iter().position(|a| *a == c).unwrap();
+            dfa.transitions[q - 1][column]
+        });
+        assert_eq!(dfa.accept.contains(&state), accept, "{}", s);
+    }
+}
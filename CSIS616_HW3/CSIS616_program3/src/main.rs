@@ -9,7 +9,54 @@
 //!
 //! cargo run filename
 //!
-//! where: `filename` is a yaml file containing the DFA definition
+//! where: `filename` is a yaml file containing the DFA definition, or a
+//! Graphviz file (.gv or .dot) such as the one this program writes
+//!
+//! cargo run moore|mealy filename [string]
+//!
+//! where: `filename` is a yaml file containing a Moore or Mealy machine
+//!
+//! cargo run 2dfa filename [string]
+//!
+//! where: `filename` is a yaml file containing a two-way DFA
+//!
+//! cargo run buchi filename [stem cycle]
+//!
+//! where: `filename` is a yaml file containing a Büchi automaton
+//!
+//! cargo run weighted filename [string [length]]
+//!
+//! where: `filename` is a yaml file containing a weighted automaton
+//!
+//! cargo run afa filename [string]
+//!
+//! where: `filename` is a yaml file containing an alternating automaton
+//!
+//! cargo run nfa filename [string]
+//!
+//! where: `filename` is a Graphviz file (.gv or .dot) drawing an NFA,
+//! ε-moves allowed, converted to a DFA by the subset construction
+//!
+//! cargo run svg filename [svgfile]
+//!
+//! where: `filename` is a yaml file containing the DFA definition, drawn to
+//! `svgfile` (the same name ending in .svg by default) without Graphviz
+//!
+//! cargo run tikz filename [texfile]
+//!
+//! where: `filename` is a yaml file containing the DFA definition, drawn to
+//! `texfile` (the same name ending in .tex by default) as a TikZ picture
+//! that compiles on its own with pdflatex
+//!
+//! cargo run mermaid dfa|moore|mealy|2dfa|buchi|weighted|afa filename
+//!
+//! where: `filename` is a yaml file containing a machine of that kind,
+//! printed as a Mermaid state diagram
+//!
+//! cargo run trace filename string [htmlfile]
+//!
+//! where: `filename` is a yaml file containing the DFA definition, whose
+//! run on `string` is drawn step by step to an HTML page
 //!
 //! # Input
 //!
@@ -17,12 +64,21 @@
 //!
 //! # Output
 //!
-//! To `stderr`: Debug display of the internal graph structure
+//! To `stderr`: Transition table and a drawing of the graph as a tree
 //!
 //! To `stdout`: Graphviz definitions of the graph structure
 //!
 //! To println : Transition steps, acceptance of the string by the graph
 
+mod afa;
+mod buchi;
+mod dot;
+mod nfa;
+mod transducer;
+mod two_way;
+mod weighted;
+
+use automata_draw::{graphviz, mermaid, output_filename, svg, terminal};
 use serde::Deserialize;
 use std::io;
 use std::io::BufRead;
@@ -77,6 +133,72 @@ struct StateGraph {
 
 // *********************************************************************
 fn main() {
+    // Moore and Mealy machines name their kind before the filename
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 2 && (args[1] == "moore" || args[1] == "mealy") {
+        run_transducer(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "2dfa" {
+        run_two_way(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "buchi" {
+        run_buchi(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "weighted" {
+        run_weighted(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "afa" {
+        run_afa(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "nfa" {
+        run_nfa(&args);
+        return;
+    }
+    if args.len() > 3 && args[1] == "mermaid" {
+        run_mermaid(&args);
+        return;
+    }
+    if args.len() > 3 && args[1] == "trace" {
+        let dfa = DFA::new_from_file(&args[2]);
+        dfa.validate().expect("Validation Failure:");
+        let state_graph = StateGraph::new_from_dfa(&dfa);
+        state_graph.check_input_alphabet(&args[3]);
+        let htmlfile = match args.get(4) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "html"),
+        };
+        state_graph.write_trace(&args[3], &htmlfile);
+        println!("Wrote {}", htmlfile);
+        return;
+    }
+    if args.len() > 2 && args[1] == "svg" {
+        let dfa = DFA::new_from_file(&args[2]);
+        dfa.validate().expect("Validation Failure:");
+        let svgfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "svg"),
+        };
+        StateGraph::new_from_dfa(&dfa).write_svg(&svgfile);
+        println!("Wrote {}", svgfile);
+        return;
+    }
+    if args.len() > 2 && args[1] == "tikz" {
+        let dfa = DFA::new_from_file(&args[2]);
+        dfa.validate().expect("Validation Failure:");
+        let texfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "tex"),
+        };
+        StateGraph::new_from_dfa(&dfa).write_tikz(&texfile);
+        println!("Wrote {}", texfile);
+        return;
+    }
+
     // Get and validat the filename on the command line
     let filename = get_filename(std::env::args());
 
@@ -90,7 +212,7 @@ fn main() {
     // Get a state structure for the DFA
     let state_graph = StateGraph::new_from_dfa(&dfa);
 
-    eprintln!("{:?}", state_graph);
+    state_graph.write_terminal();
 
     state_graph.write_graphviz();
     println!();
@@ -132,6 +254,397 @@ fn get_filename(args: std::env::Args) -> String {
     args[1].to_string()
 }
 
+// *********************************************************************
+/// Print the machine of the kind in `args[2]` from the file in `args[3]`
+/// as a Mermaid state diagram
+fn run_mermaid(args: &[String]) {
+    let filename = &args[3];
+    match args[2].as_str() {
+        "dfa" => {
+            let dfa = DFA::new_from_file(filename);
+            dfa.validate().expect("Validation Failure:");
+            StateGraph::new_from_dfa(&dfa).write_mermaid();
+        }
+        "moore" => {
+            let moore = transducer::Moore::new_from_file(filename);
+            moore.validate().expect("Validation Failure:");
+            moore.write_mermaid();
+        }
+        "mealy" => {
+            let mealy = transducer::Mealy::new_from_file(filename);
+            mealy.validate().expect("Validation Failure:");
+            mealy.write_mermaid();
+        }
+        "2dfa" => {
+            let two_way = two_way::TwoWayDFA::new_from_file(filename);
+            two_way.validate().expect("Validation Failure:");
+            two_way.write_mermaid();
+        }
+        "buchi" => {
+            let buchi = buchi::Buchi::new_from_file(filename);
+            buchi.validate().expect("Validation Failure:");
+            buchi.write_mermaid();
+        }
+        "weighted" => {
+            let weighted = weighted::Weighted::new_from_file(filename);
+            weighted.validate().expect("Validation Failure:");
+            weighted.write_mermaid();
+        }
+        "afa" => {
+            let afa = afa::AFA::new_from_file(filename);
+            afa.validate().expect("Validation Failure:");
+            afa.write_mermaid();
+        }
+        other => {
+            println!(
+                "Error: '{}' should be dfa, moore, mealy, 2dfa, buchi, weighted or afa.",
+                other
+            );
+            process::exit(1);
+        }
+    }
+}
+
+// *********************************************************************
+/// Run the Moore or Mealy machine in `args[2]` on a string, then convert
+/// it to the other kind and check that both give the same output
+fn run_transducer(args: &[String]) {
+    let (moore, mealy) = if args[1] == "moore" {
+        let moore = transducer::Moore::new_from_file(&args[2]);
+        moore.validate().expect("Validation Failure:");
+        let mealy = moore.to_mealy();
+        (moore, Box::new(mealy))
+    } else {
+        let mealy = transducer::Mealy::new_from_file(&args[2]);
+        mealy.validate().expect("Validation Failure:");
+        let moore = mealy.to_moore();
+        (Box::new(moore), mealy)
+    };
+
+    // Get string
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input.chars().any(|c| !moore.alphabet.contains(&c)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+
+    // The Moore output starts with the output of its start state
+    let start_output = moore.outputs[moore.start - 1].clone();
+    println!("Moore machine:");
+    moore.write_graphviz();
+    println!();
+    let moore_output = moore.run(&str_input, true);
+    println!("Output: {}", moore_output);
+    println!();
+
+    println!("Mealy machine:");
+    mealy.write_graphviz();
+    println!();
+    let mealy_output = mealy.run(&str_input, true);
+    println!("Output: {}", mealy_output);
+    println!();
+
+    if moore_output == format!("{}{}", start_output, mealy_output) {
+        println!("Both machines give the same output.");
+    } else {
+        println!("Error: the machines give different outputs.");
+        process::exit(1);
+    }
+}
+
+// *********************************************************************
+/// Run the 2DFA in `args[2]` on a string, then convert it to a one-way DFA
+/// and check that both accept or reject it
+fn run_two_way(args: &[String]) {
+    let two_way = two_way::TwoWayDFA::new_from_file(&args[2]);
+    two_way.validate().expect("Validation Failure:");
+
+    println!("Two-way DFA:");
+    two_way.write_graphviz();
+    println!();
+
+    // Get string
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input.chars().any(|c| !two_way.alphabet.contains(&c)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+
+    let outcome = two_way.run(&str_input, true);
+    println!();
+    println!("{}", outcome);
+    println!();
+
+    // Shepherdson's construction
+    let conversion = two_way.to_dfa();
+    conversion.dfa.validate().expect("Validation Failure:");
+    let state_graph = StateGraph::new_from_dfa(&conversion.dfa);
+    println!(
+        "One-way DFA ({} states from {}):",
+        conversion.dfa.transitions.len(),
+        two_way.transitions.len()
+    );
+    state_graph.write_graphviz();
+    println!();
+    conversion.print_tables();
+    println!();
+
+    let accept = state_graph.check_string(Vec::new(), str_input);
+    println!();
+    if accept == (outcome == two_way::Outcome::Accept) {
+        println!("Both machines agree.");
+    } else {
+        println!("Error: the machines disagree on the string.");
+        process::exit(1);
+    }
+}
+
+// *********************************************************************
+/// Check the Büchi automaton in `args[2]` for emptiness, then decide
+/// whether it accepts the word `stem(cycle)^ω`
+fn run_buchi(args: &[String]) {
+    let buchi = buchi::Buchi::new_from_file(&args[2]);
+    buchi.validate().expect("Validation Failure:");
+
+    buchi.write_graphviz();
+    println!();
+
+    match buchi.find_lasso() {
+        Some(lasso) => println!("The language is not empty, it contains {}", lasso),
+        None => println!("The language is empty."),
+    }
+    println!();
+
+    // Get the stem and cycle
+    let (stem, cycle) = if args.len() > 4 {
+        (args[3].clone(), args[4].clone())
+    } else {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        println!("Please enter a stem:");
+        let stem = lines.next().unwrap().unwrap();
+        println!("Please enter a cycle:");
+        let cycle = lines.next().unwrap().unwrap();
+        println!();
+        (stem, cycle)
+    };
+    if stem
+        .chars()
+        .chain(cycle.chars())
+        .any(|c| !buchi.alphabet.contains(&c))
+    {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+    if cycle.is_empty() {
+        println!("Error: The cycle cannot be empty.");
+        process::exit(1);
+    }
+
+    let word = buchi::Lasso { stem, cycle };
+    let accept = buchi.accepts(&word, true);
+    println!();
+    if accept {
+        println!("The word {} is accepted by the graph.", word);
+    } else {
+        println!("The word {} is not accepted by the graph.", word);
+    }
+}
+
+// *********************************************************************
+/// Give the weight of a string in the weighted automaton in `args[2]`,
+/// and the total weight of all strings of the same or a given length
+fn run_weighted(args: &[String]) {
+    let weighted = weighted::Weighted::new_from_file(&args[2]);
+    weighted.validate().expect("Validation Failure:");
+
+    println!("Semiring: {}", weighted.semiring);
+    weighted.write_graphviz();
+    println!();
+
+    // Get string
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input.chars().any(|c| !weighted.alphabet.contains(&c)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+    let length = match args.get(4) {
+        Some(n) => n.parse().unwrap_or_else(|_| {
+            println!("Error: The length must be a whole number.");
+            process::exit(1);
+        }),
+        None => str_input.chars().count(),
+    };
+
+    let weight = weighted.weight(&str_input, true);
+    println!();
+    println!("Weight of the string: {}", weighted.semiring.show(weight));
+    println!(
+        "Total weight of the strings of length {}: {}",
+        length,
+        weighted.semiring.show(weighted.total(length))
+    );
+}
+
+// *********************************************************************
+/// Run the AFA in `args[2]` on a string, then convert it to an NFA and a
+/// DFA and check that all three accept or reject it
+fn run_afa(args: &[String]) {
+    let afa = afa::AFA::new_from_file(&args[2]);
+    afa.validate().expect("Validation Failure:");
+
+    println!("Alternating automaton ({} states):", afa.transitions.len());
+    afa.write_graphviz();
+    println!();
+
+    // Get string
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input.chars().any(|c| !afa.alphabet.contains(&c)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+
+    let accept = afa.accepts(&str_input, true);
+    println!();
+
+    let nfa = afa.to_nfa();
+    println!("NFA ({} states):", nfa.transitions.len());
+    nfa.write_graphviz();
+    for (n, set) in nfa.sets.iter().enumerate() {
+        println!("	q{} = {}", n + 1, nfa::show_set(set));
+    }
+    println!();
+
+    let (dfa, subsets) = nfa.to_dfa();
+    dfa.validate().expect("Validation Failure:");
+    let state_graph = StateGraph::new_from_dfa(&dfa);
+    println!("DFA ({} states):", dfa.transitions.len());
+    state_graph.write_graphviz();
+    for (n, subset) in subsets.iter().enumerate() {
+        println!("	q{} = {}", n + 1, nfa::show_set(subset));
+    }
+    println!();
+
+    let dfa_accept = state_graph.check_string(Vec::new(), str_input.clone());
+    println!();
+    if accept != nfa.accepts(&str_input) || accept != dfa_accept {
+        println!("Error: the machines disagree on the string.");
+        process::exit(1);
+    }
+    if accept {
+        println!("The string is accepted by all three machines.");
+    } else {
+        println!("The string is not accepted by any of the machines.");
+    }
+}
+
+// *********************************************************************
+/// Run the NFA drawn in the Graphviz file in `args[2]` on a string, then
+/// convert it to a DFA and check that both accept or reject it
+fn run_nfa(args: &[String]) {
+    let digraph = dot::Digraph::new_from_file(&args[2]);
+    digraph.validate_nfa().expect("Validation Failure:");
+    print_renames(&digraph);
+
+    let nfa = digraph.to_nfa();
+    println!("NFA without ε-moves ({} states):", nfa.transitions.len());
+    nfa.write_graphviz();
+    if nfa.start > digraph.states.len() {
+        println!(
+            "	q{} starts in any of {}",
+            nfa.start,
+            nfa::show_set(&nfa.sets[nfa.start - 1])
+        );
+    }
+    println!();
+
+    // Get string
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input.chars().any(|c| !nfa.alphabet.contains(&c)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+
+    let (dfa, subsets) = nfa.to_dfa();
+    dfa.validate().expect("Validation Failure:");
+    let state_graph = StateGraph::new_from_dfa(&dfa);
+    println!("DFA ({} states):", dfa.transitions.len());
+    state_graph.write_graphviz();
+    for (n, subset) in subsets.iter().enumerate() {
+        println!("	q{} = {}", n + 1, nfa::show_set(subset));
+    }
+    println!();
+
+    let accept = nfa.accepts(&str_input);
+    let dfa_accept = state_graph.check_string(Vec::new(), str_input.clone());
+    println!();
+    if accept != dfa_accept {
+        println!("Error: the machines disagree on the string.");
+        process::exit(1);
+    }
+    if accept {
+        println!("The string is accepted by both machines.");
+    } else {
+        println!("The string is not accepted by either machine.");
+    }
+}
+
+// *********************************************************************
+/// Write the new name of each state of `digraph` not named q1 to qn to
+/// stderr
+fn print_renames(digraph: &dot::Digraph) {
+    for (name, n) in digraph.states.iter().zip(digraph.numbers()) {
+        if *name != format!("q{}", n) {
+            eprintln!("{} is q{}", name, n);
+        }
+    }
+}
+
 // *********************************************************************
 /// Implement the methods of the DFA structure
 impl DFA {
@@ -139,8 +652,17 @@ impl DFA {
     ///
     /// Load the .yaml file specified into a DFA structure
     /// on the heap and return a point to it via a Box.
-
+    ///
+    /// A .gv or .dot file is read as the digraph of a DFA, states not
+    /// named q1 to qn being renumbered.
     fn new_from_file(filename: &str) -> Box<DFA> {
+        if filename.ends_with(".gv") || filename.ends_with(".dot") {
+            let digraph = dot::Digraph::new_from_file(filename);
+            digraph.validate().expect("Validation Failure:");
+            print_renames(&digraph);
+            return Box::new(digraph.to_dfa());
+        }
+
         let f = std::fs::File::open(filename).expect("Unable to open input");
 
         // Deserialize into the heap and return the pointer
@@ -237,18 +759,145 @@ impl StateGraph {
         println!("\tnode [shape=circle];");
         println!("\tstart -> q{}", self.start_state + 1);
 
+        self.edges().write();
+
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    fn write_mermaid(&self) {
+        let mut diagram = mermaid::StateDiagram::new(&format!("q{}", self.start_state + 1));
+        for (n, state) in self.states.iter().enumerate() {
+            if state.accept_state {
+                diagram.accept.push(format!("q{}", n + 1));
+            }
+        }
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every character
+    fn edges(&self) -> graphviz::Edges {
+        let mut edges = graphviz::Edges::new();
         for (n, state) in self.states.iter().enumerate() {
             for (i, ch) in self.alphabet.iter().enumerate() {
-                println!(
-                    "\tq{} -> q{} [label=\"{}\"];",
-                    n + 1,
-                    state.transitions[i] + 1,
-                    ch
+                edges.add(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", state.transitions[i] + 1),
+                    &ch.to_string(),
                 );
             }
         }
+        edges
+    }
 
-        println!("}}");
+    /// Write the transition table and a drawing of the graph to stderr,
+    /// out of the way of the Graphviz output
+    fn write_terminal(&self) {
+        let columns = self.alphabet.iter().map(|c| c.to_string()).collect();
+        let mut table = terminal::Table::new(columns);
+        let (mut states, mut accept) = (Vec::new(), Vec::new());
+        for (n, state) in self.states.iter().enumerate() {
+            let name = format!("q{}", n + 1);
+            let entries = state
+                .transitions
+                .iter()
+                .map(|t| format!("q{}", t + 1))
+                .collect();
+            table.row(&name, n == self.start_state, state.accept_state, entries);
+            if state.accept_state {
+                accept.push(name.clone());
+            }
+            states.push(name);
+        }
+        eprint!("{}", table.render());
+        eprintln!();
+        let start = format!("q{}", self.start_state + 1);
+        eprint!(
+            "{}",
+            terminal::draw(&states, &start, &accept, &self.edges())
+        );
+    }
+
+    /// Draw the graph to the SVG file `filename`
+    fn write_svg(&self, filename: &str) {
+        std::fs::write(filename, self.diagram().to_svg()).expect("Unable to write output");
+    }
+
+    /// Draw the graph to the LaTeX file `filename`
+    fn write_tikz(&self, filename: &str) {
+        std::fs::write(filename, self.diagram().to_tikz()).expect("Unable to write output");
+    }
+
+    /// Draw the run on `s` to the HTML file `filename`, one frame per
+    /// character with the current state and the edge just taken
+    /// highlighted
+    fn write_trace(&self, s: &str, filename: &str) {
+        let diagram = self.diagram();
+        let frames: Vec<String> = self
+            .trace(s)
+            .iter()
+            .map(|highlight| diagram.to_svg_with(highlight))
+            .collect();
+        let html = svg::animation_html(&format!("Run on \"{}\"", s), &frames);
+        std::fs::write(filename, html).expect("Unable to write output");
+    }
+
+    /// What each frame of the run on `s` picks out, the start and then
+    /// one frame per character
+    fn trace(&self, s: &str) -> Vec<svg::Highlight> {
+        let mut frames = Vec::new();
+        let mut state = self.start_state;
+        let mut highlight = svg::Highlight {
+            states: vec![format!("q{}", state + 1)],
+            edges: Vec::new(),
+            caption: vec![format!("Start in q{}", state + 1)],
+        };
+        let chars: Vec<char> = s.chars().collect();
+        for n in 0..=chars.len() {
+            let read: String = chars[..n].iter().collect();
+            let left: String = chars[n..].iter().collect();
+            highlight.caption.push(format!("Read: {}", read));
+            highlight.caption.push(format!("Left: {}", left));
+            if n == chars.len() {
+                highlight.caption.push(if self.states[state].accept_state {
+                    "The string is accepted by the graph.".to_string()
+                } else {
+                    "The string is not accepted by the graph.".to_string()
+                });
+            }
+            frames.push(std::mem::take(&mut highlight));
+
+            if let Some(&c) = chars.get(n) {
+                let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+                let next = self.states[state].transitions[i];
+                highlight = svg::Highlight {
+                    states: vec![format!("q{}", next + 1)],
+                    edges: vec![(format!("q{}", state + 1), format!("q{}", next + 1))],
+                    caption: vec![format!("d(q{}, {}) -> q{}", state + 1, c, next + 1)],
+                };
+                state = next;
+            }
+        }
+        frames
+    }
+
+    /// The graph for drawing without Graphviz
+    fn diagram(&self) -> svg::Diagram {
+        let mut diagram = svg::Diagram::new();
+        diagram.start(&format!("q{}", self.start_state + 1));
+        for (n, state) in self.states.iter().enumerate() {
+            diagram.state(&format!("q{}", n + 1), state.accept_state);
+        }
+        for (n, state) in self.states.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                diagram.edge(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", state.transitions[i] + 1),
+                    &ch.to_string(),
+                );
+            }
+        }
+        diagram
     }
 
     // checks that the input string only contains symbols from the alphabet
@@ -334,4 +983,20 @@ fn test_input_alphabet_function() {
         }
         assert_eq!(contains, true);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_trace_frames() {
+    let dfa = DFA::new_from_file("sample.yaml");
+    let frames = StateGraph::new_from_dfa(&dfa).trace("xyx");
+    assert_eq!(frames.len(), 4);
+
+    let edge = |from: &str, to: &str| vec![(from.to_string(), to.to_string())];
+    let states: Vec<&str> = frames.iter().map(|f| f.states[0].as_str()).collect();
+    assert_eq!(states, ["q1", "q1", "q2", "q2"]);
+    assert!(frames[0].edges.is_empty());
+    assert_eq!(frames[1].edges, edge("q1", "q1"));
+    assert_eq!(frames[2].edges, edge("q1", "q2"));
+    assert_eq!(frames[3].edges, edge("q2", "q2"));
+    assert!(frames.iter().all(|f| f.states.len() == 1));
+}
@@ -0,0 +1,120 @@
+//! Nondeterministic finite automata
+//!
+//! Built from an alternating automaton or a Graphviz digraph, and
+//! converted to a DFA by the subset construction.
+
+use crate::DFA;
+use automata_draw::graphviz::Edges;
+use std::collections::BTreeSet;
+
+// *********************************************************************
+/// # Nondeterministic Finite Automata Structure
+#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct NFA {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Matrix of transitions, rows are states, columns characters in the
+    /// alphabet, each entry a list of states
+    pub transitions: Vec<Vec<Vec<usize>>>,
+
+    /// The states of the machine it was built from that each NFA state
+    /// stands for, as `AFA::to_nfa` or `Digraph::to_nfa` describes
+    pub sets: Vec<BTreeSet<usize>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the NFA structure
+impl NFA {
+    /// Is `input` accepted
+    pub fn accepts(&self, input: &str) -> bool {
+        let mut states: BTreeSet<usize> = std::iter::once(self.start).collect();
+        for c in input.chars() {
+            let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+            states = states
+                .iter()
+                .flat_map(|q| self.transitions[q - 1][i].iter().copied())
+                .collect();
+        }
+        states.iter().any(|q| self.accept.contains(q))
+    }
+
+    /// Equivalent DFA by the subset construction
+    ///
+    /// Returns the DFA and the NFA states in each of its states.
+    pub fn to_dfa(&self) -> (DFA, Vec<BTreeSet<usize>>) {
+        let mut subsets: Vec<BTreeSet<usize>> = vec![std::iter::once(self.start).collect()];
+        let mut transitions: Vec<Vec<usize>> = Vec::new();
+        let mut n = 0;
+        while n < subsets.len() {
+            let row = (0..self.alphabet.len())
+                .map(|i| {
+                    let next: BTreeSet<usize> = subsets[n]
+                        .iter()
+                        .flat_map(|q| self.transitions[q - 1][i].iter().copied())
+                        .collect();
+                    match subsets.iter().position(|s| *s == next) {
+                        Some(p) => p + 1,
+                        None => {
+                            subsets.push(next);
+                            subsets.len()
+                        }
+                    }
+                })
+                .collect();
+            transitions.push(row);
+            n += 1;
+        }
+
+        let dfa = DFA {
+            alphabet: self.alphabet.clone(),
+            start: 1,
+            accept: (1..=subsets.len())
+                .filter(|s| subsets[s - 1].iter().any(|q| self.accept.contains(q)))
+                .collect(),
+            transitions,
+        };
+        (dfa, subsets)
+    }
+
+    /// Write the graph to stdout
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape=doublecircle]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                for state in &row[i] {
+                    edges.add(
+                        &format!("q{}", n + 1),
+                        &format!("q{}", state),
+                        &ch.to_string(),
+                    );
+                }
+            }
+        }
+        edges.write();
+        println!("}}");
+    }
+}
+
+// *********************************************************************
+/// Printable form of a set of states, like `{q1, q3}`
+pub fn show_set(states: &BTreeSet<usize>) -> String {
+    let names: Vec<String> = states.iter().map(|q| format!("q{}", q)).collect();
+    format!("{{{}}}", names.join(", "))
+}
@@ -0,0 +1,337 @@
+//! Moore and Mealy machines
+//!
+//! Both use the DFA transition table: rows are states (1 relative),
+//! columns characters in the alphabet. A Moore machine has an output per
+//! state and starts its output with the one of the start state; a Mealy
+//! machine has an output per transition. Outputs are strings and may be
+//! empty.
+
+use automata_draw::graphviz::{escape, Edges};
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+
+// *********************************************************************
+/// # Moore Machine Structure
+#[derive(Debug, Deserialize)]
+pub struct Moore {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Output of each state
+    pub outputs: Vec<String>,
+
+    /// Matrix of transitions, rows are states, columns characters in the alphabet
+    pub transitions: Vec<Vec<usize>>,
+}
+
+// *********************************************************************
+/// # Mealy Machine Structure
+#[derive(Debug, Deserialize)]
+pub struct Mealy {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Matrix of transitions, rows are states, columns characters in the alphabet
+    pub transitions: Vec<Vec<usize>>,
+
+    /// Matrix of outputs, one for each transition
+    pub outputs: Vec<Vec<String>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Moore structure
+impl Moore {
+    /// Create and return a Moore machine on the heap
+    pub fn new_from_file(filename: &str) -> Box<Moore> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the Moore machine
+    pub fn validate(&self) -> Result<(), String> {
+        validate_table(&self.alphabet, self.start, &self.transitions)?;
+        if self.outputs.len() != self.transitions.len() {
+            return Err(format!(
+                "Wrong number of outputs({}), should be one per state({})",
+                self.outputs.len(),
+                self.transitions.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Output for `input`, printing each step when `print` is set
+    pub fn run(&self, input: &str, print: bool) -> String {
+        let mut state = self.start;
+        let mut output = self.outputs[state - 1].clone();
+        if print {
+            println!("Transition steps:");
+            println!("q{} / {}", state, show(&output));
+        }
+        for c in input.chars() {
+            let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+            let next = self.transitions[state - 1][i];
+            let out = &self.outputs[next - 1];
+            if print {
+                println!("d(q{}, {}) -> q{} / {}", state, c, next, show(out));
+            }
+            output.push_str(out);
+            state = next;
+        }
+        output
+    }
+
+    /// Equivalent Mealy machine, each transition giving the output of the
+    /// state it enters
+    ///
+    /// Its output leaves off the output of the start state.
+    pub fn to_mealy(&self) -> Mealy {
+        Mealy {
+            alphabet: self.alphabet.clone(),
+            start: self.start,
+            transitions: self.transitions.clone(),
+            outputs: self
+                .transitions
+                .iter()
+                .map(|row| row.iter().map(|q| self.outputs[q - 1].clone()).collect())
+                .collect(),
+        }
+    }
+
+    /// Write the graph to stdout, states labelled like `q1/0`
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        println!("\tnode [shape=circle];");
+        for (n, out) in self.outputs.iter().enumerate() {
+            println!("\tq{} [label=\"q{}/{}\"];", n + 1, n + 1, escape(show(out)));
+        }
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        for (n, out) in self.outputs.iter().enumerate() {
+            diagram
+                .labels
+                .push((format!("q{}", n + 1), format!("q{}/{}", n + 1, show(out))));
+        }
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every character
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                edges.add(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", row[i]),
+                    &ch.to_string(),
+                );
+            }
+        }
+        edges
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Mealy structure
+impl Mealy {
+    /// Create and return a Mealy machine on the heap
+    pub fn new_from_file(filename: &str) -> Box<Mealy> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the Mealy machine
+    pub fn validate(&self) -> Result<(), String> {
+        validate_table(&self.alphabet, self.start, &self.transitions)?;
+        if self.outputs.len() != self.transitions.len() {
+            return Err(format!(
+                "Wrong number of output rows({}), should be {}",
+                self.outputs.len(),
+                self.transitions.len()
+            ));
+        }
+        for (rnum, row) in self.outputs.iter().enumerate() {
+            if row.len() != self.alphabet.len() {
+                return Err(format!(
+                    "Wrong number of outputs({}) in row {}, should be {}",
+                    row.len(),
+                    rnum + 1,
+                    self.alphabet.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Output for `input`, printing each step when `print` is set
+    pub fn run(&self, input: &str, print: bool) -> String {
+        let mut state = self.start;
+        let mut output = String::new();
+        if print {
+            println!("Transition steps:");
+        }
+        for c in input.chars() {
+            let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+            let next = self.transitions[state - 1][i];
+            let out = &self.outputs[state - 1][i];
+            if print {
+                println!("d(q{}, {}) -> q{} / {}", state, c, next, show(out));
+            }
+            output.push_str(out);
+            state = next;
+        }
+        output
+    }
+
+    /// Equivalent Moore machine
+    ///
+    /// Its states are the pairs of a Mealy state and the output of a
+    /// transition into it, numbered as they are reached from the start
+    /// state, which is paired with the empty output.
+    pub fn to_moore(&self) -> Moore {
+        let mut pairs: Vec<(usize, String)> = vec![(self.start, String::new())];
+        let mut transitions = Vec::new();
+        let mut n = 0;
+        while n < pairs.len() {
+            let state = pairs[n].0;
+            let row = (0..self.alphabet.len())
+                .map(|i| {
+                    let pair = (
+                        self.transitions[state - 1][i],
+                        self.outputs[state - 1][i].clone(),
+                    );
+                    match pairs.iter().position(|p| *p == pair) {
+                        Some(p) => p + 1,
+                        None => {
+                            pairs.push(pair);
+                            pairs.len()
+                        }
+                    }
+                })
+                .collect();
+            transitions.push(row);
+            n += 1;
+        }
+
+        Moore {
+            alphabet: self.alphabet.clone(),
+            start: 1,
+            outputs: pairs.into_iter().map(|(_, out)| out).collect(),
+            transitions,
+        }
+    }
+
+    /// Write the graph to stdout, transitions labelled like `a/1`
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    pub fn write_mermaid(&self) {
+        StateDiagram::new(&format!("q{}", self.start)).write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every character and
+    /// its output
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                edges.add(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", row[i]),
+                    &format!("{}/{}", ch, show(&self.outputs[n][i])),
+                );
+            }
+        }
+        edges
+    }
+}
+
+// *********************************************************************
+/// Validate a transition table the way the DFA does
+pub fn validate_table(
+    alphabet: &[char],
+    start: usize,
+    transitions: &[Vec<usize>],
+) -> Result<(), String> {
+    for (rnum, row) in transitions.iter().enumerate() {
+        if row.len() != alphabet.len() {
+            return Err(format!(
+                "Wrong number of columns({}) in row {}, should be {}",
+                row.len(),
+                rnum + 1,
+                alphabet.len()
+            ));
+        }
+        for (cnum, state) in row.iter().enumerate() {
+            if *state == 0 || *state > transitions.len() {
+                return Err(format!(
+                    "Invalid transition state({}) in row {}, column {}",
+                    state,
+                    rnum + 1,
+                    cnum + 1
+                ));
+            }
+        }
+    }
+    if start == 0 || start > transitions.len() {
+        return Err(format!("Start state({}), is not valid", start));
+    }
+    Ok(())
+}
+
+// *********************************************************************
+/// Printable form of an output, ε when it is empty
+fn show(output: &str) -> &str {
+    if output.is_empty() {
+        "ε"
+    } else {
+        output
+    }
+}
+
+// Test Functions
+#[test]
+fn test_mealy_moore_conversion() {
+    // Mealy machine giving 1 when the last two characters are equal
+    let mealy: Mealy = serde_yaml::from_str(
+        "alphabet: ['a', 'b']\nstart: 1\ntransitions: [[2, 3], [2, 3], [2, 3]]\noutputs: [[0, 0], [1, 0], [0, 1]]",
+    )
+    .unwrap();
+    assert_eq!(mealy.validate(), Ok(()));
+    assert_eq!(mealy.run("aabbab", false), "010100");
+
+    let moore = mealy.to_moore();
+    assert_eq!(moore.validate(), Ok(()));
+    assert_eq!(moore.run("aabbab", false), "010100");
+
+    let back = moore.to_mealy();
+    for input in ["", "a", "abba", "bbbaab"] {
+        assert_eq!(back.run(input, false), mealy.run(input, false));
+    }
+}
@@ -0,0 +1,385 @@
+//! Two-way deterministic finite automata
+//!
+//! The input is read between the endmarkers `<` and `>`, starting on `<`
+//! in the start state. Each transition gives the next state and whether
+//! the head moves left or right. The machine may never move left from
+//! `<`; it halts when it moves right from `>` and accepts when the state
+//! it moves into is an accept state. A machine that comes back to the
+//! same state on the same cell loops forever and does not accept.
+
+use crate::DFA;
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Left endmarker
+pub const LEFT_END: char = '<';
+
+/// Right endmarker
+pub const RIGHT_END: char = '>';
+
+// *********************************************************************
+/// # Direction the head moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Direction {
+    /// One cell to the left
+    L,
+
+    /// One cell to the right
+    R,
+}
+
+// *********************************************************************
+/// # Two-way Deterministic Finite Automata Structure
+#[derive(Debug, Deserialize)]
+pub struct TwoWayDFA {
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Matrix of transitions, rows are states, columns `<`, the characters
+    /// in the alphabet, then `>`
+    pub transitions: Vec<Vec<(usize, Direction)>>,
+}
+
+// *********************************************************************
+/// # How a run of a 2DFA ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Moved right from `>` into an accept state
+    Accept,
+
+    /// Moved right from `>` into any other state
+    Reject,
+
+    /// Came back to a state and cell it was in before
+    Loop,
+}
+
+// *********************************************************************
+/// # One-way DFA built from a 2DFA
+#[derive(Debug)]
+pub struct Conversion {
+    /// The DFA, in the same form as one read from a file
+    pub dfa: DFA,
+
+    /// Behaviour table of the prefix read so far, for each DFA state
+    ///
+    /// Entry 0 is the state in which the 2DFA, started on `<`, first moves
+    /// right off the prefix; entry `q` the state in which it next moves
+    /// right off it after moving left into its last cell in state `q`.
+    /// None means it never moves right off the prefix.
+    pub tables: Vec<Vec<Option<usize>>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the TwoWayDFA structure
+impl TwoWayDFA {
+    /// Create and return a 2DFA on the heap
+    pub fn new_from_file(filename: &str) -> Box<TwoWayDFA> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the 2DFA
+    pub fn validate(&self) -> Result<(), String> {
+        if self.alphabet.contains(&LEFT_END) || self.alphabet.contains(&RIGHT_END) {
+            return Err(format!(
+                "The endmarkers {} and {} cannot be in the alphabet",
+                LEFT_END, RIGHT_END
+            ));
+        }
+
+        // One column for each endmarker and each character in the alphabet
+        for (rnum, row) in self.transitions.iter().enumerate() {
+            if row.len() != self.alphabet.len() + 2 {
+                return Err(format!(
+                    "Wrong number of columns({}) in row {}, should be {}",
+                    row.len(),
+                    rnum + 1,
+                    self.alphabet.len() + 2
+                ));
+            }
+            for (cnum, (state, _)) in row.iter().enumerate() {
+                if *state == 0 || *state > self.transitions.len() {
+                    return Err(format!(
+                        "Invalid transition state({}) in row {}, column {}",
+                        state,
+                        rnum + 1,
+                        cnum + 1
+                    ));
+                }
+            }
+            if row[0].1 == Direction::L {
+                return Err(format!("Row {} moves left from {}", rnum + 1, LEFT_END));
+            }
+        }
+
+        // The start and accept states must be valid
+        if self.start == 0 || self.start > self.transitions.len() {
+            return Err(format!("Start state({}), is not valid", self.start));
+        }
+        for acc_state in self.accept.iter() {
+            if *acc_state == 0 || *acc_state > self.transitions.len() {
+                return Err(format!("Accept state({}), is not valid", acc_state));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Transition from `state` (1 relative) reading `c`
+    fn delta(&self, state: usize, c: char) -> (usize, Direction) {
+        let column = if c == LEFT_END {
+            0
+        } else if c == RIGHT_END {
+            self.alphabet.len() + 1
+        } else {
+            self.alphabet.iter().position(|a| *a == c).unwrap() + 1
+        };
+        self.transitions[state - 1][column]
+    }
+
+    /// Run on `input`, printing each step when `print` is set
+    pub fn run(&self, input: &str, print: bool) -> Outcome {
+        let tape: Vec<char> = std::iter::once(LEFT_END)
+            .chain(input.chars())
+            .chain(std::iter::once(RIGHT_END))
+            .collect();
+        let tape_string: String = tape.iter().collect();
+        let mut seen = HashSet::new();
+        let (mut state, mut head) = (self.start, 0);
+
+        if print {
+            println!("Transition steps:");
+        }
+        loop {
+            if !seen.insert((state, head)) {
+                return Outcome::Loop;
+            }
+            let (next, direction) = self.delta(state, tape[head]);
+            if print {
+                println!(
+                    "{}\t{:w$}^ d(q{}, {}) -> (q{}, {:?})",
+                    tape_string,
+                    "",
+                    state,
+                    tape[head],
+                    next,
+                    direction,
+                    w = head
+                );
+            }
+            state = next;
+            match direction {
+                Direction::L => head -= 1,
+                Direction::R if head + 1 == tape.len() => {
+                    return if self.accept.contains(&state) {
+                        Outcome::Accept
+                    } else {
+                        Outcome::Reject
+                    };
+                }
+                Direction::R => head += 1,
+            }
+        }
+    }
+
+    /// Build an equivalent one-way DFA (Shepherdson's construction)
+    ///
+    /// Each DFA state is the behaviour table of the input read so far;
+    /// only the tables reachable from the table of `<` alone are built.
+    pub fn to_dfa(&self) -> Conversion {
+        let n = self.transitions.len();
+
+        // reading < alone: the 2DFA can only move right off it
+        let first: Vec<Option<usize>> = std::iter::once(self.start)
+            .chain(1..=n)
+            .map(|q| Some(self.delta(q, LEFT_END).0))
+            .collect();
+
+        let mut tables = vec![first];
+        let mut transitions: Vec<Vec<usize>> = Vec::new();
+        let mut i = 0;
+        while i < tables.len() {
+            let row = self
+                .alphabet
+                .iter()
+                .map(|c| {
+                    let next = self.extend(&tables[i], *c);
+                    match tables.iter().position(|t| *t == next) {
+                        Some(p) => p + 1,
+                        None => {
+                            tables.push(next);
+                            tables.len()
+                        }
+                    }
+                })
+                .collect();
+            transitions.push(row);
+            i += 1;
+        }
+
+        // at > the 2DFA either falls off the end or moves back left
+        let accept = tables
+            .iter()
+            .enumerate()
+            .filter(|(_, table)| {
+                let mut seen = HashSet::new();
+                let mut arriving = table[0];
+                while let Some(state) = arriving {
+                    if !seen.insert(state) {
+                        break;
+                    }
+                    match self.delta(state, RIGHT_END) {
+                        (q, Direction::R) => return self.accept.contains(&q),
+                        (q, Direction::L) => arriving = table[q],
+                    }
+                }
+                false
+            })
+            .map(|(i, _)| i + 1)
+            .collect();
+
+        Conversion {
+            dfa: DFA {
+                alphabet: self.alphabet.clone(),
+                start: 1,
+                accept,
+                transitions,
+            },
+            tables,
+        }
+    }
+
+    /// Behaviour table after reading `c` past a prefix with `table`
+    fn extend(&self, table: &[Option<usize>], c: char) -> Vec<Option<usize>> {
+        // arriving on the new cell in some state, when does the 2DFA
+        // move right off it
+        let leave = |arriving: Option<usize>| {
+            let mut seen = HashSet::new();
+            let mut current = arriving?;
+            loop {
+                if !seen.insert(current) {
+                    return None;
+                }
+                match self.delta(current, c) {
+                    (q, Direction::R) => return Some(q),
+                    (q, Direction::L) => current = table[q]?,
+                }
+            }
+        };
+        std::iter::once(leave(table[0]))
+            .chain((1..=self.transitions.len()).map(|q| leave(Some(q))))
+            .collect()
+    }
+
+    /// Write the graph to stdout, transitions labelled like `a R`
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape=doublecircle]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.accept = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every column and the
+    /// way the head moves
+    fn edges(&self) -> Edges {
+        let columns: Vec<char> = std::iter::once(LEFT_END)
+            .chain(self.alphabet.iter().copied())
+            .chain(std::iter::once(RIGHT_END))
+            .collect();
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, (state, direction)) in row.iter().enumerate() {
+                edges.add(
+                    &format!("q{}", n + 1),
+                    &format!("q{}", state),
+                    &format!("{} {:?}", columns[i], direction),
+                );
+            }
+        }
+        edges
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Conversion structure
+impl Conversion {
+    /// Print the behaviour table of every DFA state
+    pub fn print_tables(&self) {
+        let show = |entry: &Option<usize>| match entry {
+            Some(q) => format!("q{}", q),
+            None => "-".to_string(),
+        };
+        println!("Behaviour tables (start, then re-entering in q1, q2, ...):");
+        for (n, table) in self.tables.iter().enumerate() {
+            let entries: Vec<String> = table.iter().map(show).collect();
+            println!("\tq{} = [{}]", n + 1, entries.join(", "));
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Accept => write!(f, "The string is accepted by the 2DFA."),
+            Outcome::Reject => write!(f, "The string is not accepted by the 2DFA."),
+            Outcome::Loop => write!(f, "The 2DFA loops forever, so the string is not accepted."),
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_two_way_to_dfa() {
+    // second character from the end is an a
+    let two_way = TwoWayDFA::new_from_file("2dfa.yaml");
+    assert_eq!(two_way.validate(), Ok(()));
+    let conversion = two_way.to_dfa();
+    assert_eq!(conversion.dfa.validate(), Ok(()));
+
+    let graph = crate::StateGraph::new_from_dfa(&conversion.dfa);
+    for input in ["", "a", "ab", "ba", "bab", "aaa", "abb", "babab"] {
+        let expected = input.len() >= 2 && input.chars().rev().nth(1) == Some('a');
+        assert_eq!(
+            two_way.run(input, false) == Outcome::Accept,
+            expected,
+            "{}",
+            input
+        );
+        let accepted = graph.check_string(Vec::new(), input.to_string());
+        assert_eq!(accepted, expected, "{}", input);
+    }
+
+    // bouncing between < and the first a
+    let looping: TwoWayDFA = serde_yaml::from_str(
+        "alphabet: ['a']\nstart: 1\naccept: [1]\ntransitions: [[[1, R], [1, L], [1, R]]]",
+    )
+    .unwrap();
+    assert_eq!(looping.run("", false), Outcome::Accept);
+    assert_eq!(looping.run("a", false), Outcome::Loop);
+    assert!(looping.to_dfa().dfa.accept == vec![1]);
+}
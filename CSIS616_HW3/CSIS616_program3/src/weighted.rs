@@ -0,0 +1,305 @@
+//! Weighted finite automata
+//!
+//! Like an NFA, each state has a list of moves for each character in the
+//! alphabet, and each move carries a weight. The weight of a path is the
+//! product of its weights and the weight of a string the sum over its
+//! accepting paths, with product and sum taken in the semiring named in
+//! the file.
+
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+use std::fmt;
+
+// *********************************************************************
+/// # Semirings the weights can be taken in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Semiring {
+    /// Probabilities in [0, 1] with + and ×
+    Probability,
+
+    /// Costs with min and +, the cheapest path wins
+    Tropical,
+
+    /// Whole numbers with + and ×, counting paths
+    Counting,
+}
+
+// *********************************************************************
+/// # Weighted Finite Automata Structure
+#[derive(Debug, Deserialize)]
+pub struct Weighted {
+    /// Semiring the weights are taken in
+    pub semiring: Semiring,
+
+    /// The set of characters comprising the alphabet
+    pub alphabet: Vec<char>,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Matrix of transitions, rows are states, columns characters in the
+    /// alphabet, each entry a list of [state, weight] moves
+    pub transitions: Vec<Vec<Vec<(usize, f64)>>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Semiring enumeration
+impl Semiring {
+    /// Identity of plus
+    pub fn zero(self) -> f64 {
+        match self {
+            Semiring::Tropical => f64::INFINITY,
+            _ => 0.0,
+        }
+    }
+
+    /// Identity of times
+    pub fn one(self) -> f64 {
+        match self {
+            Semiring::Tropical => 0.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Combine the weights of two alternative paths
+    pub fn plus(self, x: f64, y: f64) -> f64 {
+        match self {
+            Semiring::Tropical => x.min(y),
+            _ => x + y,
+        }
+    }
+
+    /// Combine the weights of two steps along a path
+    pub fn times(self, x: f64, y: f64) -> f64 {
+        match self {
+            Semiring::Tropical => x + y,
+            _ => x * y,
+        }
+    }
+
+    /// Printable form of a weight
+    pub fn show(self, weight: f64) -> String {
+        if weight == f64::INFINITY {
+            "∞".to_string()
+        } else {
+            format!("{}", weight)
+        }
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Weighted structure
+impl Weighted {
+    /// Create and return a weighted automaton on the heap
+    pub fn new_from_file(filename: &str) -> Box<Weighted> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the weighted automaton
+    pub fn validate(&self) -> Result<(), String> {
+        for (rnum, row) in self.transitions.iter().enumerate() {
+            if row.len() != self.alphabet.len() {
+                return Err(format!(
+                    "Wrong number of columns({}) in row {}, should be {}",
+                    row.len(),
+                    rnum + 1,
+                    self.alphabet.len()
+                ));
+            }
+            for (cnum, moves) in row.iter().enumerate() {
+                for (state, weight) in moves {
+                    if *state == 0 || *state > self.transitions.len() {
+                        return Err(format!(
+                            "Invalid transition state({}) in row {}, column {}",
+                            state,
+                            rnum + 1,
+                            cnum + 1
+                        ));
+                    }
+                    let valid = match self.semiring {
+                        Semiring::Probability => (0.0..=1.0).contains(weight),
+                        Semiring::Tropical => !weight.is_nan(),
+                        Semiring::Counting => *weight >= 0.0 && weight.fract() == 0.0,
+                    };
+                    if !valid {
+                        return Err(format!(
+                            "Invalid {:?} weight({}) in row {}, column {}",
+                            self.semiring,
+                            weight,
+                            rnum + 1,
+                            cnum + 1
+                        ));
+                    }
+                }
+            }
+        }
+
+        // The start and accept states must be valid
+        if self.start == 0 || self.start > self.transitions.len() {
+            return Err(format!("Start state({}), is not valid", self.start));
+        }
+        for acc_state in self.accept.iter() {
+            if *acc_state == 0 || *acc_state > self.transitions.len() {
+                return Err(format!("Accept state({}), is not valid", acc_state));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Weight of reaching each state from `weights` on any of `columns`
+    fn forward(&self, weights: &[f64], columns: &[usize]) -> Vec<f64> {
+        let s = self.semiring;
+        let mut next = vec![s.zero(); weights.len()];
+        for (q, row) in self.transitions.iter().enumerate() {
+            for &i in columns {
+                for (to, w) in &row[i] {
+                    next[to - 1] = s.plus(next[to - 1], s.times(weights[q], *w));
+                }
+            }
+        }
+        next
+    }
+
+    /// Weights of the paths from the start state, before any input
+    fn initial(&self) -> Vec<f64> {
+        let mut weights = vec![self.semiring.zero(); self.transitions.len()];
+        weights[self.start - 1] = self.semiring.one();
+        weights
+    }
+
+    /// Sum of the weights in the accept states
+    fn accepted(&self, weights: &[f64]) -> f64 {
+        self.accept.iter().fold(self.semiring.zero(), |total, q| {
+            self.semiring.plus(total, weights[q - 1])
+        })
+    }
+
+    /// Weight of `input`, printing the weight of each state after every
+    /// character when `print` is set
+    pub fn weight(&self, input: &str, print: bool) -> f64 {
+        let mut weights = self.initial();
+        if print {
+            println!("Weights of each state:");
+            self.print_weights("ε", &weights);
+        }
+        let mut prefix = String::new();
+        for c in input.chars() {
+            let i = self.alphabet.iter().position(|a| *a == c).unwrap();
+            weights = self.forward(&weights, &[i]);
+            prefix.push(c);
+            if print {
+                self.print_weights(&prefix, &weights);
+            }
+        }
+        self.accepted(&weights)
+    }
+
+    /// Sum of the weights of all strings of length `n`
+    pub fn total(&self, n: usize) -> f64 {
+        let columns: Vec<usize> = (0..self.alphabet.len()).collect();
+        let mut weights = self.initial();
+        for _ in 0..n {
+            weights = self.forward(&weights, &columns);
+        }
+        self.accepted(&weights)
+    }
+
+    /// Print one line of weights, after reading `prefix`
+    fn print_weights(&self, prefix: &str, weights: &[f64]) {
+        let entries: Vec<String> = weights
+            .iter()
+            .enumerate()
+            .map(|(q, w)| format!("q{}: {}", q + 1, self.semiring.show(*w)))
+            .collect();
+        println!("\t{}\t{}", prefix, entries.join(", "));
+    }
+
+    /// Write the graph to stdout, transitions labelled like `a/0.5`
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape=doublecircle]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.accept = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every character and
+    /// its weight
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for (n, row) in self.transitions.iter().enumerate() {
+            for (i, ch) in self.alphabet.iter().enumerate() {
+                for (state, weight) in &row[i] {
+                    edges.add(
+                        &format!("q{}", n + 1),
+                        &format!("q{}", state),
+                        &format!("{}/{}", ch, self.semiring.show(*weight)),
+                    );
+                }
+            }
+        }
+        edges
+    }
+}
+
+impl fmt::Display for Semiring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Semiring::Probability => write!(f, "probability (+, ×)"),
+            Semiring::Tropical => write!(f, "tropical (min, +)"),
+            Semiring::Counting => write!(f, "counting (+, ×)"),
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_weighted_semirings() {
+    // bigram model: every string of a length shares probability 1
+    let bigram = Weighted::new_from_file("weighted.yaml");
+    assert_eq!(bigram.validate(), Ok(()));
+    assert!((bigram.weight("ab", false) - 0.05).abs() < 1e-9);
+    for n in 0..5 {
+        assert!((bigram.total(n) - 1.0).abs() < 1e-9);
+    }
+
+    // cheapest way to read the string, each b costs 1
+    let cost: Weighted = serde_yaml::from_str(
+        "semiring: tropical\nalphabet: ['a', 'b']\nstart: 1\naccept: [1]\ntransitions: [[[[1, 0]], [[1, 1]]]]",
+    )
+    .unwrap();
+    assert_eq!(cost.validate(), Ok(()));
+    assert_eq!(cost.weight("abba", false), 2.0);
+    assert_eq!(cost.total(3), 0.0);
+
+    // paths guessing which a is the last one
+    let paths: Weighted = serde_yaml::from_str(
+        "semiring: counting\nalphabet: ['a', 'b']\nstart: 1\naccept: [2]\ntransitions: [[[[1, 1], [2, 1]], [[1, 1]]], [[], []]]",
+    )
+    .unwrap();
+    assert_eq!(paths.validate(), Ok(()));
+    assert_eq!(paths.weight("aba", false), 1.0);
+    assert_eq!(paths.weight("ab", false), 0.0);
+    assert_eq!(paths.total(3), 4.0);
+}
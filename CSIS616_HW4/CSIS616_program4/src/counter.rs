@@ -0,0 +1,289 @@
+//! Counter machines
+//!
+//! A program is a list of instructions numbered from 1 like states, run
+//! from the first one, on counters numbered from 1 that hold non-negative
+//! whole numbers. An instruction either increments a counter, or
+//! decrements it and branches elsewhere when it is already zero. The
+//! machine stops when it reaches a halt instruction. Two counters are
+//! enough to simulate any Turing machine.
+
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use serde::Deserialize;
+use std::fmt;
+
+// *********************************************************************
+/// # A single instruction of a counter machine
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Instruction {
+    /// Add one to `counter`, then go to `next`
+    Inc { counter: usize, next: usize },
+
+    /// Go to `zero` when `counter` is zero, otherwise subtract one from it
+    /// and go to `next`
+    Dec {
+        counter: usize,
+        next: usize,
+        zero: usize,
+    },
+
+    /// Stop
+    Halt,
+}
+
+// *********************************************************************
+/// # Counter Machine Structure
+#[derive(Debug, Deserialize)]
+pub struct CounterMachine {
+    /// Number of counters
+    pub counters: usize,
+
+    /// Instructions, the first one runs first
+    pub program: Vec<Instruction>,
+}
+
+// *********************************************************************
+/// # Current instruction and counter values of a running machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Configuration {
+    /// Instruction about to run (1 relative)
+    pub instruction: usize,
+
+    /// Value of each counter
+    pub values: Vec<u64>,
+}
+
+// *********************************************************************
+/// # How a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Reached a halt instruction
+    Halt,
+
+    /// Still running after the given number of steps
+    NoHalt(usize),
+}
+
+// *********************************************************************
+/// Implement the methods of the CounterMachine structure
+impl CounterMachine {
+    /// Create and return a counter machine on the heap
+    pub fn new_from_file(filename: &str) -> Box<CounterMachine> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the counter machine
+    pub fn validate(&self) -> Result<(), String> {
+        if self.program.is_empty() {
+            return Err("The program has no instructions".to_string());
+        }
+        for (n, instruction) in self.program.iter().enumerate() {
+            let (counter, targets) = match instruction {
+                Instruction::Inc { counter, next } => (*counter, vec![*next]),
+                Instruction::Dec {
+                    counter,
+                    next,
+                    zero,
+                } => (*counter, vec![*next, *zero]),
+                Instruction::Halt => continue,
+            };
+            if counter == 0 || counter > self.counters {
+                return Err(format!(
+                    "Invalid counter({}) in instruction {}",
+                    counter,
+                    n + 1
+                ));
+            }
+            for target in targets {
+                if target == 0 || target > self.program.len() {
+                    return Err(format!(
+                        "Invalid next instruction({}) in instruction {}",
+                        target,
+                        n + 1
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Configuration before the first step, with the counters starting at
+    /// `values` and any left out at zero
+    pub fn initial(&self, values: &[u64]) -> Configuration {
+        let mut start = vec![0; self.counters];
+        start[..values.len()].copy_from_slice(values);
+        Configuration {
+            instruction: 1,
+            values: start,
+        }
+    }
+
+    /// Configuration after running one instruction, none after a halt
+    pub fn step(&self, config: &Configuration) -> Option<Configuration> {
+        let mut next = config.clone();
+        match self.program[config.instruction - 1] {
+            Instruction::Inc { counter, next: to } => {
+                next.values[counter - 1] += 1;
+                next.instruction = to;
+            }
+            Instruction::Dec {
+                counter,
+                next: to,
+                zero,
+            } => {
+                if next.values[counter - 1] == 0 {
+                    next.instruction = zero;
+                } else {
+                    next.values[counter - 1] -= 1;
+                    next.instruction = to;
+                }
+            }
+            Instruction::Halt => return None,
+        }
+        Some(next)
+    }
+
+    /// Run from `values` for at most `limit` steps, returning how the run
+    /// ended and every configuration it went through
+    pub fn run(&self, values: &[u64], limit: usize) -> (Outcome, Vec<Configuration>) {
+        let mut trace = vec![self.initial(values)];
+        while trace.len() <= limit {
+            match self.step(trace.last().unwrap()) {
+                Some(next) => trace.push(next),
+                None => return (Outcome::Halt, trace),
+            }
+        }
+        (Outcome::NoHalt(limit), trace)
+    }
+
+    /// Print every step of a run with the counter values
+    pub fn print_trace(&self, trace: &[Configuration]) {
+        println!("Counter values:");
+        for config in trace {
+            println!(
+                "\t{}\tq{}: {}",
+                config,
+                config.instruction,
+                self.program[config.instruction - 1]
+            );
+        }
+    }
+
+    /// Write the control graph to stdout
+    ///
+    /// Edges are labelled with what happens to the counter: `c1+` for an
+    /// increment, `c1-` for a decrement and `c1=0` for the branch taken
+    /// when it is zero.
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for (n, instruction) in self.program.iter().enumerate() {
+            if *instruction == Instruction::Halt {
+                println!("\tnode [shape=doublecircle]; q{};", n + 1);
+            }
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q1");
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the control graph to stdout as a Mermaid state diagram, halt
+    /// instructions as accept states
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new("q1");
+        for (n, instruction) in self.program.iter().enumerate() {
+            if *instruction == Instruction::Halt {
+                diagram.accept.push(format!("q{}", n + 1));
+            }
+        }
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of instructions, labelled with what happens to
+    /// the counter
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for (n, instruction) in self.program.iter().enumerate() {
+            let from = format!("q{}", n + 1);
+            match instruction {
+                Instruction::Inc { counter, next } => {
+                    edges.add(&from, &format!("q{}", next), &format!("c{}+", counter));
+                }
+                Instruction::Dec {
+                    counter,
+                    next,
+                    zero,
+                } => {
+                    edges.add(&from, &format!("q{}", next), &format!("c{}-", counter));
+                    edges.add(&from, &format!("q{}", zero), &format!("c{}=0", counter));
+                }
+                Instruction::Halt => (),
+            }
+        }
+        edges
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Inc { counter, next } => write!(f, "inc c{}, goto q{}", counter, next),
+            Instruction::Dec {
+                counter,
+                next,
+                zero,
+            } => write!(f, "dec c{}, goto q{}, or q{} if zero", counter, next, zero),
+            Instruction::Halt => write!(f, "halt"),
+        }
+    }
+}
+
+impl fmt::Display for Configuration {
+    /// Counter values, like `(3, 0)`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<String> = self.values.iter().map(|v| v.to_string()).collect();
+        write!(f, "({})", values.join(", "))
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Halt => write!(f, "The machine halted."),
+            Outcome::NoHalt(n) => write!(f, "The machine did not halt within {} steps.", n),
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_counter_machine_runs() {
+    // doubles c1 using c2
+    let machine = CounterMachine::new_from_file("counter.yaml");
+    assert_eq!(machine.validate(), Ok(()));
+    let (outcome, trace) = machine.run(&[5], 1000);
+    assert_eq!(outcome, Outcome::Halt);
+    assert_eq!(trace.last().unwrap().values, vec![10, 0]);
+    assert_eq!(trace.last().unwrap().to_string(), "(10, 0)");
+    assert_eq!(machine.run(&[0], 1000).0, Outcome::Halt);
+    assert_eq!(machine.run(&[5], 10).0, Outcome::NoHalt(10));
+
+    // counts up forever
+    let forever: CounterMachine =
+        serde_yaml::from_str("counters: 1\nprogram: [{op: inc, counter: 1, next: 1}]").unwrap();
+    assert_eq!(forever.validate(), Ok(()));
+    let (outcome, trace) = forever.run(&[], 20);
+    assert_eq!(outcome, Outcome::NoHalt(20));
+    assert_eq!(trace.last().unwrap().values, vec![20]);
+
+    let broken: CounterMachine =
+        serde_yaml::from_str("counters: 1\nprogram: [{op: dec, counter: 2, next: 1, zero: 1}]")
+            .unwrap();
+    assert!(broken.validate().is_err());
+}
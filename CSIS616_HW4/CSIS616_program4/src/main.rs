@@ -1,4 +1,12 @@
+mod counter;
+mod single_tape;
+mod tm;
+
+use automata_draw::{graphviz, mermaid, output_filename, svg, terminal};
 use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::BufRead;
 use std::io::Write;
 
 // ***********************************************************************
@@ -15,7 +23,56 @@ struct PDA {
     accept: Vec<usize>,
 
     /// Matrix of transitions, rows are states, columns characters in the alphabet
+    #[serde(default)]
     transitions: Vec<Vec<usize>>,
+
+    /// Characters that may be pushed on the stack, any when empty
+    #[serde(default)]
+    stack_alphabet: Vec<char>,
+
+    /// Moves of the PDA, read from `transitions` when none are given
+    #[serde(default)]
+    moves: Vec<Move>,
+}
+
+// *********************************************************************
+/// # A single move of the PDA
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Move {
+    /// State number (1 relative) the move leaves
+    from: usize,
+
+    /// State number (1 relative) the move enters
+    to: usize,
+
+    /// Input character read, none for an ε-move
+    #[serde(default)]
+    read: Option<char>,
+
+    /// Character popped off the stack, none to leave the stack alone
+    #[serde(default)]
+    pop: Option<char>,
+
+    /// Characters pushed on the stack, the first ends up on top
+    #[serde(default)]
+    push: String,
+}
+
+/// Most configurations searched for a computation to trace
+const TRACE_LIMIT: usize = 10000;
+
+// *********************************************************************
+/// # State, input read and stack of a running PDA
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Configuration {
+    /// State number (1 relative)
+    state: usize,
+
+    /// Number of input characters read
+    read: usize,
+
+    /// Stack contents, top first
+    stack: String,
 }
 
 // *********************************************************************
@@ -44,8 +101,81 @@ struct StateGraph {
 }
 
 fn main() {
+    // Turing machines are run with: tm tmfile [string] [steps]
+    // and converted to a single tape with: single tmfile
+    // Counter machines are run with: counter file [values] [steps]
+    // A PDA is drawn to an SVG file with: svg pdafile [svgfile]
+    // or to a LaTeX file with TikZ with: tikz pdafile [texfile]
+    // and its run to an HTML page with: trace pdafile string [htmlfile]
+    // Machines are printed as Mermaid with: mermaid pda|tm|counter file
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 2 && args[1] == "counter" {
+        run_counter(&args);
+        return;
+    }
+    if args.len() > 2 && args[1] == "tm" {
+        run_tm(&args);
+        return;
+    }
+    if args.len() > 3 && args[1] == "mermaid" {
+        run_mermaid(&args);
+        return;
+    }
+    if args.len() > 3 && args[1] == "trace" {
+        let pda = PDA::new_from_file(&args[2]);
+        pda.validate().expect("Validation Failure:");
+        if args[3].chars().any(|c| !pda.alphabet.contains(&c)) {
+            println!("Error: Character not in alphabet.");
+            std::process::exit(1);
+        }
+        let htmlfile = match args.get(4) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "html"),
+        };
+        StateGraph::new_from_pda(&pda).write_trace(&pda, &args[3], &htmlfile);
+        println!("Wrote {}", htmlfile);
+        return;
+    }
+    if args.len() > 2 && args[1] == "svg" {
+        let pda = PDA::new_from_file(&args[2]);
+        pda.validate().expect("Validation Failure:");
+        let svgfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "svg"),
+        };
+        StateGraph::new_from_pda(&pda).write_svg(&pda, &svgfile);
+        println!("Wrote {}", svgfile);
+        return;
+    }
+    if args.len() > 2 && args[1] == "tikz" {
+        let pda = PDA::new_from_file(&args[2]);
+        pda.validate().expect("Validation Failure:");
+        let texfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "tex"),
+        };
+        StateGraph::new_from_pda(&pda).write_tikz(&pda, &texfile);
+        println!("Wrote {}", texfile);
+        return;
+    }
+    if args.len() == 3 && args[1] == "single" {
+        let machine = tm::TM::new_from_file(&args[2]);
+        machine.validate().expect("Validation Failure:");
+        let single = single_tape::SingleTape::new(&machine);
+        println!(
+            "Single-tape machine: {} states, {} transitions",
+            single.machine.states,
+            single.machine.transitions.len()
+        );
+        println!();
+        single
+            .machine
+            .write_graphviz_with(|c| single.symbol_name(c));
+        return;
+    }
+
     // Get and validat the filename on the command line
-    let filename = get_filename(std::env::args());
+    let (filename, strict) = get_filename(std::env::args());
 
     // Load the yaml file getting a Box pointing to a DFA
     // instance on the heap
@@ -54,14 +184,30 @@ fn main() {
     // Validate the DFA
     pda.validate().expect("Validation Failure:");
 
+    // Report whether the PDA is deterministic, refusing it in strict mode
+    let conflicts = pda.conflicts();
+    if conflicts.is_empty() {
+        println!("The PDA is deterministic.");
+    } else {
+        println!("The PDA is not deterministic:");
+        for (first, second) in &conflicts {
+            println!("\t{}  conflicts with  {}", first, second);
+        }
+        if strict {
+            println!("Error: strict mode only accepts a deterministic PDA");
+            std::process::exit(1);
+        }
+    }
+    println!();
+
     println!("{:?}", pda);
 
     // Get a state structure for the DFA
     let state_graph = StateGraph::new_from_pda(&pda);
 
-    // 4. graph printed to debug format
+    // 4. transition table and drawing of the graph to stderr
     println!();
-    eprintln!("{:?}", state_graph);
+    state_graph.write_terminal(&pda);
     println!();
 
     // 5. stdout GraphViz definition
@@ -69,17 +215,165 @@ fn main() {
 }
 
 // *********************************************************************
-/// Return the filename passed as the first parameter
-fn get_filename(args: std::env::Args) -> String {
+/// Return the filename passed as the last parameter and whether
+/// `--strict` came before it
+fn get_filename(args: std::env::Args) -> (String, bool) {
     // Get the arguments as a vector
     let args: Vec<String> = args.collect();
 
-    // Make sure only one argument was passed
-    if args.len() != 2 {
-        writeln!(std::io::stderr(), "Usage: hw1 dfafile").unwrap();
+    // Make sure only the filename, optionally after --strict, was passed
+    let strict = args.len() == 3 && args[1] == "--strict";
+    if args.len() != 2 && !strict {
+        writeln!(std::io::stderr(), "Usage: hw4 [--strict] pdafile").unwrap();
         std::process::exit(1);
     }
-    args[1].to_string()
+    (args[args.len() - 1].to_string(), strict)
+}
+
+// *********************************************************************
+/// Print the machine of the kind in `args[2]` from the file in `args[3]`
+/// as a Mermaid state diagram
+fn run_mermaid(args: &[String]) {
+    let filename = &args[3];
+    match args[2].as_str() {
+        "pda" => {
+            let pda = PDA::new_from_file(filename);
+            pda.validate().expect("Validation Failure:");
+            StateGraph::new_from_pda(&pda).write_mermaid(&pda);
+        }
+        "tm" => {
+            let machine = tm::TM::new_from_file(filename);
+            machine.validate().expect("Validation Failure:");
+            machine.write_mermaid();
+        }
+        "counter" => {
+            let machine = counter::CounterMachine::new_from_file(filename);
+            machine.validate().expect("Validation Failure:");
+            machine.write_mermaid();
+        }
+        other => {
+            println!("Error: '{}' should be pda, tm or counter.", other);
+            std::process::exit(1);
+        }
+    }
+}
+
+// *********************************************************************
+/// Load, draw and run the Turing machine in `args[2]`
+///
+/// The string is prompted for when `args[3]` is missing and the run stops
+/// after `args[4]` steps, 1000 by default. A nondeterministic machine is
+/// searched through at most that many configurations instead.
+fn run_tm(args: &[String]) {
+    let machine = tm::TM::new_from_file(&args[2]);
+    machine.validate().expect("Validation Failure:");
+
+    machine.write_graphviz();
+    println!();
+
+    let str_input = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter a string:");
+            let stdin = std::io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    if str_input
+        .chars()
+        .any(|c| !machine.input_alphabet.contains(&c))
+    {
+        println!("Error: Character not in alphabet.");
+        std::process::exit(1);
+    }
+    let limit: usize = match args.get(4) {
+        Some(n) => n.parse().expect("Step limit must be a number"),
+        None => 1000,
+    };
+
+    // A nondeterministic machine shows its first accepting computation
+    let (outcome, trace) = machine.simulate(&str_input, limit);
+    if machine.is_deterministic() {
+        machine.print_trace(&trace);
+        println!();
+    } else if outcome == tm::Outcome::Accept {
+        println!("Accepting computation:");
+        machine.print_trace(&trace);
+        println!();
+    }
+    println!("{}", outcome);
+
+    // A multi-tape machine is checked against its single-tape version
+    if machine.tapes > 1 {
+        let single = single_tape::SingleTape::new(&machine);
+        println!();
+        println!(
+            "Single-tape machine: {} states, {} transitions",
+            single.machine.states,
+            single.machine.transitions.len()
+        );
+        match single_tape::compare(&machine, &single, &str_input, limit) {
+            Some(true) => println!("Both machines agree."),
+            Some(false) => {
+                println!("Error: the machines do not agree.");
+                std::process::exit(1);
+            }
+            None => println!(
+                "The {}-tape machine did not halt within the limit, so the machines are not compared.",
+                machine.tapes
+            ),
+        }
+    }
+}
+
+// *********************************************************************
+/// Load, draw and run the counter machine in `args[2]`
+///
+/// The starting counter values in `args[3]` are separated by commas and
+/// prompted for when missing; the run stops after `args[4]` steps, 1000
+/// by default.
+fn run_counter(args: &[String]) {
+    let machine = counter::CounterMachine::new_from_file(&args[2]);
+    machine.validate().expect("Validation Failure:");
+
+    machine.write_graphviz();
+    println!();
+
+    let line = match args.get(3) {
+        Some(s) => s.clone(),
+        None => {
+            println!("Please enter the starting counter values:");
+            let stdin = std::io::stdin();
+            let line = stdin.lock().lines().next().unwrap().unwrap();
+            println!();
+            line
+        }
+    };
+    let values: Vec<u64> = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse().unwrap_or_else(|_| {
+                println!("Error: Counter values must be whole numbers.");
+                std::process::exit(1);
+            })
+        })
+        .collect();
+    if values.len() > machine.counters {
+        println!("Error: The machine only has {} counters.", machine.counters);
+        std::process::exit(1);
+    }
+    let limit: usize = match args.get(4) {
+        Some(n) => n.parse().expect("Step limit must be a number"),
+        None => 1000,
+    };
+
+    let (outcome, trace) = machine.run(&values, limit);
+    machine.print_trace(&trace);
+    println!();
+    println!("{}", outcome);
 }
 
 // *********************************************************************
@@ -111,7 +405,7 @@ impl PDA {
         // Validate that all states in the transition table are valid
         for (rnum, row) in self.transitions.iter().enumerate() {
             for (cnum, state) in row.iter().enumerate() {
-                if *state as usize > self.transitions.len() {
+                if *state == 0 {
                     return Err(format!(
                         "Invalid transition state({}) in row {}, column {}",
                         state,
@@ -123,18 +417,213 @@ impl PDA {
         }
 
         // The start and accept states must be valid
-        if self.start as usize > self.transitions.len() {
+        if self.start == 0 || self.start > self.state_count() {
             return Err(format!("Start state({}), is not valid", self.start));
         }
 
         for acc_state in self.accept.iter() {
-            if *acc_state as usize > self.transitions.len() {
+            if *acc_state == 0 || *acc_state > self.state_count() {
                 return Err(format!("Accept state({}), is not valid", acc_state));
             }
         }
 
+        // Moves must read from the alphabet and use the stack alphabet
+        for (n, m) in self.moves.iter().enumerate() {
+            if m.from == 0 || m.to == 0 {
+                return Err(format!("Invalid state in move {}: {}", n + 1, m));
+            }
+            if let Some(c) = m.read {
+                if !self.alphabet.contains(&c) {
+                    return Err(format!(
+                        "Move {} reads '{}', which is not in the alphabet",
+                        n + 1,
+                        c
+                    ));
+                }
+            }
+            if !self.stack_alphabet.is_empty() {
+                for c in m
+                    .pop
+                    .iter()
+                    .chain(m.push.chars().collect::<Vec<char>>().iter())
+                {
+                    if !self.stack_alphabet.contains(c) {
+                        return Err(format!(
+                            "Move {} uses '{}', which is not in the stack alphabet",
+                            n + 1,
+                            c
+                        ));
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
+
+    /// Number of states, the highest state named in any pair of
+    /// `transitions` or any move
+    fn state_count(&self) -> usize {
+        self.transitions
+            .iter()
+            .flatten()
+            .copied()
+            .chain(self.moves.iter().map(|m| m.from.max(m.to)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The moves of the PDA
+    ///
+    /// A file with only `transitions` lists pairs of states; their moves
+    /// are the ones of the HW4 machine: the start state pushes `$`, state 2
+    /// pushes what it reads, other self loops pop what they read and the
+    /// move into an accept state pops `$`.
+    fn moves(&self) -> Vec<Move> {
+        if !self.moves.is_empty() {
+            return self.moves.clone();
+        }
+
+        let mut moves = Vec::new();
+        for transition in &self.transitions {
+            let (from, to) = (transition[0], transition[1]);
+            let mv = |read, pop, push: Option<char>| Move {
+                from,
+                to,
+                read,
+                pop,
+                push: push.map(|c| c.to_string()).unwrap_or_default(),
+            };
+            if from == self.start {
+                moves.push(mv(None, None, Some('$')));
+            } else if self.accept.contains(&to) {
+                moves.push(mv(None, Some('$'), None));
+            } else if from != to {
+                moves.push(mv(None, None, None));
+            } else if from == 2 {
+                for letter in &self.alphabet {
+                    moves.push(mv(Some(*letter), None, Some(*letter)));
+                }
+            } else {
+                for letter in &self.alphabet {
+                    moves.push(mv(Some(*letter), Some(*letter), None));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Search breadth-first for a computation accepting `input`, looking
+    /// at no more than `limit` configurations
+    ///
+    /// Returns whether one was found and the configurations of the
+    /// computation, each with the move that led to it. Without one the
+    /// computation that read the most input is returned.
+    fn run(&self, input: &str, limit: usize) -> (bool, Vec<(Option<Move>, Configuration)>) {
+        let chars: Vec<char> = input.chars().collect();
+        let moves = self.moves();
+        let start = Configuration {
+            state: self.start,
+            read: 0,
+            stack: String::new(),
+        };
+
+        // how each configuration was first reached
+        let mut parent: HashMap<Configuration, Option<(Configuration, Move)>> = HashMap::new();
+        parent.insert(start.clone(), None);
+        let mut queue = VecDeque::from(vec![start.clone()]);
+        let mut furthest = start;
+        let mut accepted = None;
+        while let Some(config) = queue.pop_front() {
+            if config.read == chars.len() && self.accept.contains(&config.state) {
+                accepted = Some(config);
+                break;
+            }
+            if config.read > furthest.read {
+                furthest = config.clone();
+            }
+            for m in moves.iter().filter(|m| m.from == config.state) {
+                if m.read.is_some() && m.read != chars.get(config.read).copied() {
+                    continue;
+                }
+                if m.pop.is_some() && m.pop != config.stack.chars().next() {
+                    continue;
+                }
+                let rest = match m.pop {
+                    Some(c) => &config.stack[c.len_utf8()..],
+                    None => &config.stack[..],
+                };
+                let next = Configuration {
+                    state: m.to,
+                    read: config.read + m.read.map_or(0, |_| 1),
+                    stack: format!("{}{}", m.push, rest),
+                };
+                if parent.len() < limit && !parent.contains_key(&next) {
+                    parent.insert(next.clone(), Some((config.clone(), m.clone())));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let found = accepted.is_some();
+        let mut config = accepted.unwrap_or(furthest);
+        let mut path = Vec::new();
+        while let Some((before, m)) = parent[&config].clone() {
+            path.push((Some(m), config));
+            config = before;
+        }
+        path.push((None, config));
+        path.reverse();
+        (found, path)
+    }
+
+    /// Every pair of moves that can both be taken from one configuration
+    ///
+    /// Two moves from the same state clash when they read the same
+    /// character or one of them is an ε-move, and they pop the same
+    /// character or one of them leaves the stack alone.
+    fn conflicts(&self) -> Vec<(Move, Move)> {
+        let moves = self.moves();
+        let mut conflicts = Vec::new();
+        for (i, first) in moves.iter().enumerate() {
+            for second in &moves[i + 1..] {
+                let overlap =
+                    |a: Option<char>, b: Option<char>| a.is_none() || b.is_none() || a == b;
+                if first.from == second.from
+                    && overlap(first.read, second.read)
+                    && overlap(first.pop, second.pop)
+                {
+                    conflicts.push((first.clone(), second.clone()));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Move structure
+impl Move {
+    /// Graphviz label such as `x, e -> x`, with `e` for nothing
+    fn label(&self) -> String {
+        let push = if self.push.is_empty() {
+            "e".to_string()
+        } else {
+            self.push.clone()
+        };
+        format!(
+            "{}, {} -> {}",
+            self.read.unwrap_or('e'),
+            self.pop.unwrap_or('e'),
+            push
+        )
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "q{} -> q{} ({})", self.from, self.to, self.label())
+    }
 }
 
 // *********************************************************************
@@ -149,75 +638,181 @@ impl StateGraph {
             states: vec![],
         });
 
-        // Look through the transition table building state objects
-        for row in pda.transitions.iter() {
+        // Moves give each state the states (0 relative) it can move to
+        let moves = pda.moves();
+        for state in 1..=pda.state_count() {
             let mut v = Box::new(State {
                 accept_state: false,
                 transitions: vec![],
             });
-            for col in row {
-                v.transitions.push(*col);
+            for m in moves.iter().filter(|m| m.from == state) {
+                if !v.transitions.contains(&(m.to - 1)) {
+                    v.transitions.push(m.to - 1);
+                }
             }
             graph.states.push(v);
         }
 
         // Set the accept states
         for astate in pda.accept.iter() {
-            graph.states[*astate].accept_state = true;
+            graph.states[*astate - 1].accept_state = true;
         }
 
         graph
     }
 
-    /// Write the graph to stdout
+    /// Write the graph to stdout, one line per move on shared edges
     fn write_graphviz(&self, pda: &PDA) {
         println!("digraph {{");
         println!("\trankdir=LR;");
         println!("\tnode [shape=point]; start;");
         for (n, state) in self.states.iter().enumerate() {
             if state.accept_state {
-                println!("\tnode [shape=doublecircle]; q{};", n);
+                println!("\tnode [shape=doublecircle]; q{};", n + 1);
             }
         }
         println!("\tnode [shape=circle];");
         println!("\tstart -> q{}", self.start_state);
 
-        for transition in &pda.transitions {
-            if transition[0] == self.start_state {
-                // first state
-                println!(
-                    "\tq{} -> q{} [label=\"{}, {} -> {}\"];",
-                    self.start_state, transition[1], "e", "e", "$"
-                );
-            } else if pda.accept.iter().any(|&i| i == transition[1]) {
-                // if it it entering accept state
-                println!(
-                    "\tq{} -> q{} [label=\"{}, {} -> {}\"];",
-                    transition[0], transition[1], "e", "$", "e"
-                );
-            } else if transition[0] != transition[1] {
-                // if it is transitioning
-                println!(
-                    "\tq{} -> q{} [label=\"{}, {} -> {}\"];",
-                    transition[0], transition[1], "e", "e", "e"
-                );
-            } else if transition[0] == transition[1] && transition[0] == 2 {
-                for letter in &pda.alphabet {
-                    println!(
-                        "\tq{} -> q{} [label=\"{}, {} -> {}\"];",
-                        transition[0], transition[1], letter, "e", letter
-                    );
-                }
-            } else {
-                for letter in &pda.alphabet {
-                    println!(
-                        "\tq{} -> q{} [label=\"{}, {} -> {}\"];",
-                        transition[0], transition[1], letter, letter, "e"
-                    );
+        self.edges(pda).write_with("\\n", None, "");
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    fn write_mermaid(&self, pda: &PDA) {
+        let mut diagram = mermaid::StateDiagram::new(&format!("q{}", self.start_state));
+        for (n, state) in self.states.iter().enumerate() {
+            if state.accept_state {
+                diagram.accept.push(format!("q{}", n + 1));
+            }
+        }
+        diagram.write(&self.edges(pda));
+    }
+
+    /// One edge per pair of states, labelled with every move
+    fn edges(&self, pda: &PDA) -> graphviz::Edges {
+        let mut edges = graphviz::Edges::new();
+        for m in pda.moves() {
+            edges.add(&format!("q{}", m.from), &format!("q{}", m.to), &m.label());
+        }
+        edges
+    }
+
+    /// Write the transition table and a drawing of the graph to stderr,
+    /// out of the way of the Graphviz output
+    ///
+    /// Each entry of the table is a move like `$/a$ → q2`, popping `$`,
+    /// pushing `a$` and entering q2, one line per move.
+    fn write_terminal(&self, pda: &PDA) {
+        let moves = pda.moves();
+        let mut reads: Vec<Option<char>> = pda.alphabet.iter().map(|c| Some(*c)).collect();
+        if moves.iter().any(|m| m.read.is_none()) {
+            reads.push(None);
+        }
+        let columns = reads.iter().map(|r| r.unwrap_or('e').to_string()).collect();
+        let mut table = terminal::Table::new(columns);
+        let (mut states, mut accept) = (Vec::new(), Vec::new());
+        for (n, state) in self.states.iter().enumerate() {
+            let name = format!("q{}", n + 1);
+            let entries = reads
+                .iter()
+                .map(|read| {
+                    let lines: Vec<String> = moves
+                        .iter()
+                        .filter(|m| m.from == n + 1 && m.read == *read)
+                        .map(|m| {
+                            let push = if m.push.is_empty() { "e" } else { &m.push };
+                            format!("{}/{} → q{}", m.pop.unwrap_or('e'), push, m.to)
+                        })
+                        .collect();
+                    lines.join("\n")
+                })
+                .collect();
+            table.row(
+                &name,
+                n + 1 == self.start_state,
+                state.accept_state,
+                entries,
+            );
+            if state.accept_state {
+                accept.push(name.clone());
+            }
+            states.push(name);
+        }
+        eprint!("{}", table.render());
+        eprintln!();
+        let start = format!("q{}", self.start_state);
+        eprint!(
+            "{}",
+            terminal::draw(&states, &start, &accept, &self.edges(pda))
+        );
+    }
+
+    /// Draw the graph to the SVG file `filename`
+    fn write_svg(&self, pda: &PDA, filename: &str) {
+        std::fs::write(filename, self.diagram(pda).to_svg()).expect("Unable to write output");
+    }
+
+    /// Draw the graph to the LaTeX file `filename`
+    fn write_tikz(&self, pda: &PDA, filename: &str) {
+        std::fs::write(filename, self.diagram(pda).to_tikz()).expect("Unable to write output");
+    }
+
+    /// Draw a computation on `s` to the HTML file `filename`, one frame
+    /// per move with the current state and the move just taken
+    /// highlighted
+    fn write_trace(&self, pda: &PDA, s: &str, filename: &str) {
+        let diagram = self.diagram(pda);
+        let chars: Vec<char> = s.chars().collect();
+        let (accepted, path) = pda.run(s, TRACE_LIMIT);
+        let mut frames = Vec::new();
+        for (n, (m, config)) in path.iter().enumerate() {
+            let mut highlight = svg::Highlight {
+                states: vec![format!("q{}", config.state)],
+                edges: Vec::new(),
+                caption: Vec::new(),
+            };
+            match m {
+                Some(m) => {
+                    highlight
+                        .edges
+                        .push((format!("q{}", m.from), format!("q{}", m.to)));
+                    highlight.caption.push(m.to_string());
                 }
+                None => highlight.caption.push("Start".to_string()),
+            }
+            let read: String = chars[..config.read].iter().collect();
+            let left: String = chars[config.read..].iter().collect();
+            highlight.caption.push(format!("Read: {}", read));
+            highlight.caption.push(format!("Left: {}", left));
+            highlight.caption.push(format!("Stack: {}", config.stack));
+            if n + 1 == path.len() {
+                highlight.caption.push(if accepted {
+                    "The string is accepted by the PDA.".to_string()
+                } else {
+                    format!(
+                        "The string is not accepted within {} configurations, this run read the most of it.",
+                        TRACE_LIMIT
+                    )
+                });
             }
+            frames.push(diagram.to_svg_with(&highlight));
         }
-        println!("}}");
+        let html = svg::animation_html(&format!("Run on \"{}\"", s), &frames);
+        std::fs::write(filename, html).expect("Unable to write output");
+    }
+
+    /// The graph for drawing without Graphviz
+    fn diagram(&self, pda: &PDA) -> svg::Diagram {
+        let mut diagram = svg::Diagram::new();
+        diagram.start(&format!("q{}", self.start_state));
+        for (n, state) in self.states.iter().enumerate() {
+            diagram.state(&format!("q{}", n + 1), state.accept_state);
+        }
+        for m in pda.moves() {
+            diagram.edge(&format!("q{}", m.from), &format!("q{}", m.to), &m.label());
+        }
+        diagram
     }
 }
 
@@ -237,6 +832,8 @@ fn test_alphabet_loads_properly() {
         start: 1,
         accept: vec![4],
         transitions: transitions,
+        stack_alphabet: vec![],
+        moves: vec![],
     };
 
     // Get a state structure for the DFA
@@ -245,4 +842,67 @@ fn test_alphabet_loads_properly() {
     eprintln!("{:?}", state_graph);
 
     assert_eq!(pda.alphabet, state_graph.alphabet);
+    assert_eq!(pda.validate(), Ok(()));
+}
+
+#[test]
+fn test_state_numbers() {
+    // five pairs between four states
+    let pda = PDA::new_from_file("pda.yaml");
+    assert_eq!(pda.transitions.len(), 5);
+    assert_eq!(pda.state_count(), 4);
+    assert_eq!(StateGraph::new_from_pda(&pda).states.len(), 4);
+
+    // accept states are 1 relative like the start state
+    let wrong = |start, accept| PDA {
+        start,
+        accept,
+        ..*PDA::new_from_file("pda.yaml")
+    };
+    assert_eq!(
+        wrong(1, vec![0]).validate(),
+        Err("Accept state(0), is not valid".to_string())
+    );
+    assert_eq!(
+        wrong(1, vec![5]).validate(),
+        Err("Accept state(5), is not valid".to_string())
+    );
+    assert_eq!(
+        wrong(0, vec![4]).validate(),
+        Err("Start state(0), is not valid".to_string())
+    );
+}
+
+#[test]
+fn test_determinism_conflicts() {
+    // ww^R guesses the middle with an ε-move out of state 2
+    let pda = PDA::new_from_file("pda.yaml");
+    let conflicts = pda.conflicts();
+    assert_eq!(conflicts.len(), 2);
+    assert!(conflicts
+        .iter()
+        .all(|(a, b)| a.from == 2 && b.to == 3 && b.read.is_none()));
+
+    // a^n b^n reading every character with a different stack top
+    let pda = PDA::new_from_file("dpda.yaml");
+    assert_eq!(pda.validate(), Ok(()));
+    assert!(pda.conflicts().is_empty());
+}
+
+#[test]
+fn test_pda_runs() {
+    let pda = PDA::new_from_file("dpda.yaml");
+    let (accepted, path) = pda.run("aabb", 1000);
+    assert!(accepted);
+    assert_eq!(path.len(), 7);
+    assert_eq!(path[3].1.stack, "aa$");
+    assert_eq!(path[6].1.state, 4);
+    assert!(path[1..].iter().all(|(m, _)| m.is_some()));
+    assert!(!pda.run("aab", 1000).0);
+    assert_eq!(pda.run("aab", 1000).1.last().unwrap().1.read, 3);
+
+    // ww^R has to guess the middle
+    let pda = PDA::new_from_file("pda.yaml");
+    assert!(pda.run("xyyx", 1000).0);
+    assert!(!pda.run("xyx", 1000).0);
 }
@@ -0,0 +1,489 @@
+//! Conversion of a multi-tape Turing machine to a single-tape one
+//!
+//! Each cell of the single tape holds one track per tape of the original
+//! machine: the symbol on that tape and whether its head is on the cell.
+//! Cell 0 is also marked so the machine can find the left end. One step
+//! of the original machine takes three sweeps: right to read the symbols
+//! under the marked heads, back left to cell 0, then right to write them
+//! and move the heads going right, and back left moving the heads going
+//! left.
+
+use crate::tm::{Configuration, Direction, Outcome, Transition, TM};
+use std::collections::HashMap;
+use std::fmt;
+
+// *********************************************************************
+/// # Contents of one cell of the single tape
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrackCell {
+    /// Symbol on each track
+    pub symbols: Vec<char>,
+
+    /// Is the head of each track on this cell
+    pub heads: Vec<bool>,
+
+    /// Is this cell 0
+    pub first: bool,
+}
+
+// *********************************************************************
+/// # What the single-tape machine is doing, one state per value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Phase {
+    /// Encoding the input symbol in cell 0
+    Start,
+
+    /// Encoding the rest of the input
+    Encode,
+
+    /// Going back to cell 0 after encoding the input
+    Rewind,
+
+    /// Halted in an accept state
+    Accept,
+
+    /// Halted in a reject state
+    Reject,
+
+    /// Sweeping right in state `q` collecting the symbols under the heads
+    Scan(usize, Vec<Option<char>>),
+
+    /// Going back to cell 0 to apply the transition
+    Back(usize),
+
+    /// Sweeping right writing, carrying the heads that move right
+    Write(usize, Vec<bool>),
+
+    /// Sweeping left, carrying the heads that move left
+    Return(usize, Vec<bool>),
+}
+
+// *********************************************************************
+/// # A single-tape machine simulating a multi-tape one
+#[derive(Debug)]
+pub struct SingleTape {
+    /// The single-tape machine
+    pub machine: TM,
+
+    /// Track cell written as each tape symbol that is not a plain symbol
+    cells: HashMap<char, TrackCell>,
+}
+
+// *********************************************************************
+/// Implement the methods of the SingleTape structure
+impl SingleTape {
+    /// Build the single-tape machine for `tm`
+    ///
+    /// Track cells are written as letters from U+0100 on that the
+    /// original machine does not use.
+    pub fn new(tm: &TM) -> SingleTape {
+        let k = tm.tapes;
+
+        // every track cell, cell 0 or not, with each symbol and head mark
+        let mut all_cells = vec![Vec::new()];
+        for _ in 0..k {
+            all_cells = all_cells
+                .into_iter()
+                .flat_map(|cell: Vec<(char, bool)>| {
+                    tm.tape_alphabet.iter().flat_map(move |c| {
+                        let cell = cell.clone();
+                        [false, true].iter().map(move |mark| {
+                            let mut cell = cell.clone();
+                            cell.push((*c, *mark));
+                            cell
+                        })
+                    })
+                })
+                .collect();
+        }
+        let mut letters = (0x100..)
+            .filter_map(std::char::from_u32)
+            .filter(|c| c.is_alphabetic() && !tm.tape_alphabet.contains(c));
+        let mut encode: HashMap<TrackCell, char> = HashMap::new();
+        for first in [false, true] {
+            for cell in &all_cells {
+                let cell = TrackCell {
+                    symbols: cell.iter().map(|(c, _)| *c).collect(),
+                    heads: cell.iter().map(|(_, mark)| *mark).collect(),
+                    first,
+                };
+                encode.insert(cell, letters.next().unwrap());
+            }
+        }
+        let cells: HashMap<char, TrackCell> =
+            encode.iter().map(|(cell, c)| (*c, cell.clone())).collect();
+
+        let mut tape_alphabet: Vec<char> = tm.input_alphabet.clone();
+        tape_alphabet.push(tm.blank);
+        let mut encoded: Vec<char> = cells.keys().copied().collect();
+        encoded.sort_unstable();
+        tape_alphabet.extend(encoded);
+
+        // number the phases as they are reached, the start state first
+        let builder = Builder {
+            tm,
+            encode: &encode,
+            cells: &cells,
+        };
+        let mut phases = vec![Phase::Start, Phase::Accept, Phase::Reject];
+        let mut transitions = Vec::new();
+        let mut n = 0;
+        while n < phases.len() {
+            for symbol in &tape_alphabet {
+                for (to, write, direction) in builder.next(&phases[n], *symbol) {
+                    let to = match phases.iter().position(|p| *p == to) {
+                        Some(i) => i,
+                        None => {
+                            phases.push(to);
+                            phases.len() - 1
+                        }
+                    };
+                    transitions.push(Transition {
+                        from: n + 1,
+                        read: vec![*symbol],
+                        to: to + 1,
+                        write: vec![write],
+                        direction: vec![direction],
+                    });
+                }
+            }
+            n += 1;
+        }
+
+        SingleTape {
+            machine: TM {
+                states: phases.len(),
+                tapes: 1,
+                input_alphabet: tm.input_alphabet.clone(),
+                tape_alphabet,
+                blank: tm.blank,
+                start: 1,
+                accept: vec![2],
+                reject: vec![3],
+                transitions,
+            },
+            cells,
+        }
+    }
+
+    /// How a tape symbol of the single-tape machine is shown
+    ///
+    /// A track cell is written like `[0^,_]`, with `^` after the symbols
+    /// under a head and `|` in front of cell 0.
+    pub fn symbol_name(&self, c: char) -> String {
+        match self.cells.get(&c) {
+            Some(cell) => cell.to_string(),
+            None => c.to_string(),
+        }
+    }
+
+    /// The tapes and heads of the original machine stored in a
+    /// configuration of the single-tape machine
+    ///
+    /// Trailing blanks are left off the tapes.
+    pub fn decode(&self, config: &Configuration, blank: char) -> (Vec<String>, Vec<usize>) {
+        let k = self.cells.values().next().map_or(0, |c| c.symbols.len());
+        let mut tapes = vec![String::new(); k];
+        let mut heads = vec![0; k];
+        for (n, c) in config.tapes[0].iter().enumerate() {
+            if let Some(cell) = self.cells.get(c) {
+                for i in 0..k {
+                    tapes[i].push(cell.symbols[i]);
+                    if cell.heads[i] {
+                        heads[i] = n;
+                    }
+                }
+            }
+        }
+        let tapes = tapes
+            .iter()
+            .map(|t| t.trim_end_matches(blank).to_string())
+            .collect();
+        (tapes, heads)
+    }
+}
+
+impl fmt::Display for TrackCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tracks: Vec<String> = self
+            .symbols
+            .iter()
+            .zip(self.heads.iter())
+            .map(|(c, head)| {
+                if *head {
+                    format!("{}^", c)
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect();
+        let front = if self.first { "|" } else { "" };
+        write!(f, "{}[{}]", front, tracks.join(","))
+    }
+}
+
+// *********************************************************************
+/// # What the transitions of the single-tape machine are built from
+struct Builder<'a> {
+    /// The machine being simulated
+    tm: &'a TM,
+
+    /// Tape symbol for each track cell
+    encode: &'a HashMap<TrackCell, char>,
+
+    /// Track cell for each tape symbol that is one
+    cells: &'a HashMap<char, TrackCell>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Builder structure
+impl<'a> Builder<'a> {
+    /// Transitions out of `phase` reading `symbol`: the next phase, the
+    /// symbol written and the move
+    ///
+    /// Past the last cell of a scan there is one for every transition of
+    /// the original machine that applies, so a nondeterministic machine
+    /// gives a nondeterministic single-tape one.
+    fn next(&self, phase: &Phase, symbol: char) -> Vec<(Phase, char, Direction)> {
+        if let Phase::Scan(q, seen) = phase {
+            if !self.cells.contains_key(&symbol) {
+                let read: Option<Vec<char>> = seen.iter().copied().collect();
+                return match read {
+                    Some(read) => (0..self.tm.transitions.len())
+                        .filter(|n| {
+                            let t = &self.tm.transitions[*n];
+                            t.from == *q && t.read == read
+                        })
+                        .map(|n| (Phase::Back(n), symbol, Direction::L))
+                        .collect(),
+                    None => Vec::new(),
+                };
+            }
+        }
+        self.step(phase, symbol).into_iter().collect()
+    }
+
+    /// The transition out of `phase` reading `symbol` other than at the
+    /// end of a scan, or None when there is none
+    fn step(&self, phase: &Phase, symbol: char) -> Option<(Phase, char, Direction)> {
+        let tm = self.tm;
+        let k = tm.tapes;
+        let cell = self.cells.get(&symbol);
+
+        match phase {
+            Phase::Start | Phase::Encode => {
+                if cell.is_some() {
+                    return None;
+                }
+                let first = *phase == Phase::Start;
+                let mut symbols = vec![tm.blank; k];
+                symbols[0] = symbol;
+                let write = self.encode[&TrackCell {
+                    symbols,
+                    heads: vec![first; k],
+                    first,
+                }];
+                if symbol != tm.blank {
+                    Some((Phase::Encode, write, Direction::R))
+                } else if first {
+                    Some((self.enter(tm.start), write, Direction::S))
+                } else {
+                    Some((Phase::Rewind, symbol, Direction::L))
+                }
+            }
+            Phase::Rewind => match cell {
+                Some(c) if c.first => Some((self.enter(tm.start), symbol, Direction::S)),
+                Some(_) => Some((Phase::Rewind, symbol, Direction::L)),
+                None => None,
+            },
+            Phase::Accept | Phase::Reject => None,
+            Phase::Scan(q, seen) => {
+                let c = cell?;
+                let seen = (0..k)
+                    .map(|i| {
+                        if c.heads[i] {
+                            Some(c.symbols[i])
+                        } else {
+                            seen[i]
+                        }
+                    })
+                    .collect();
+                Some((Phase::Scan(*q, seen), symbol, Direction::R))
+            }
+            Phase::Back(t) => match cell {
+                Some(c) if c.first => {
+                    Some((Phase::Write(*t, vec![false; k]), symbol, Direction::S))
+                }
+                Some(_) => Some((Phase::Back(*t), symbol, Direction::L)),
+                None => None,
+            },
+            Phase::Write(t, carried) => {
+                let transition = &tm.transitions[*t];
+                match cell {
+                    Some(c) => {
+                        let mut next = c.clone();
+                        let mut carry = vec![false; k];
+                        for i in 0..k {
+                            if c.heads[i] {
+                                next.symbols[i] = transition.write[i];
+                                if transition.direction[i] == Direction::R {
+                                    next.heads[i] = false;
+                                    carry[i] = true;
+                                }
+                            }
+                            if carried[i] {
+                                next.heads[i] = true;
+                            }
+                        }
+                        Some((Phase::Write(*t, carry), self.encode[&next], Direction::R))
+                    }
+                    None if carried.contains(&true) => {
+                        // a head moved past the last cell, add a blank one
+                        let next = TrackCell {
+                            symbols: vec![tm.blank; k],
+                            heads: carried.clone(),
+                            first: false,
+                        };
+                        Some((
+                            Phase::Write(*t, vec![false; k]),
+                            self.encode[&next],
+                            Direction::R,
+                        ))
+                    }
+                    None => Some((Phase::Return(*t, vec![false; k]), symbol, Direction::L)),
+                }
+            }
+            Phase::Return(t, carried) => {
+                let transition = &tm.transitions[*t];
+                let c = cell?;
+                let mut next = c.clone();
+                let mut carry = vec![false; k];
+                for i in 0..k {
+                    if carried[i] {
+                        next.heads[i] = true;
+                    } else if c.heads[i] && transition.direction[i] == Direction::L && !c.first {
+                        next.heads[i] = false;
+                        carry[i] = true;
+                    }
+                }
+                let write = self.encode[&next];
+                if c.first {
+                    Some((self.enter(transition.to), write, Direction::S))
+                } else {
+                    Some((Phase::Return(*t, carry), write, Direction::L))
+                }
+            }
+        }
+    }
+
+    /// Phase simulating the original machine in state `q` at cell 0
+    fn enter(&self, q: usize) -> Phase {
+        if self.tm.accept.contains(&q) {
+            Phase::Accept
+        } else if self.tm.reject.contains(&q) {
+            Phase::Reject
+        } else {
+            Phase::Scan(q, vec![None; self.tm.tapes])
+        }
+    }
+}
+
+// *********************************************************************
+/// Run both machines on `input` and report whether they agree, or
+/// `None` when the original machine reaches no verdict within `limit`
+///
+/// The single-tape machine gets enough steps for the sweeps over a tape
+/// that can grow by one cell per step of the original machine. Given
+/// that many it may halt where the original was cut off, so nothing is
+/// compared then. Tapes are only compared for deterministic machines, as
+/// nondeterministic ones may accept through different computations.
+pub fn compare(tm: &TM, single: &SingleTape, input: &str, limit: usize) -> Option<bool> {
+    let (outcome, trace) = tm.simulate(input, limit);
+    if matches!(outcome, Outcome::NoHalt(_) | Outcome::NotFound(_)) {
+        println!(
+            "{}-tape machine: {} after {} steps",
+            tm.tapes,
+            outcome_word(outcome),
+            trace.len() - 1
+        );
+        return None;
+    }
+    let length = input.chars().count() + limit + 2;
+    let single_limit = 2 * length + limit * (4 * length + 4);
+    let (single_outcome, single_trace) = single.machine.simulate(input, single_limit);
+
+    println!(
+        "{}-tape machine: {} after {} steps",
+        tm.tapes,
+        outcome_word(outcome),
+        trace.len() - 1
+    );
+    println!(
+        "Single-tape machine: {} after {} steps",
+        outcome_word(single_outcome),
+        single_trace.len() - 1
+    );
+
+    let last = trace.last().unwrap();
+    let tapes: Vec<String> = last
+        .tapes
+        .iter()
+        .map(|t| {
+            let t: String = t.iter().collect();
+            t.trim_end_matches(tm.blank).to_string()
+        })
+        .collect();
+    let (single_tapes, single_heads) = single.decode(single_trace.last().unwrap(), tm.blank);
+    let deterministic = tm.is_deterministic();
+
+    let agree = match (outcome, single_outcome) {
+        (a, b) if a == b && !deterministic => true,
+        (a, b) if a == b => tapes == single_tapes && last.heads == single_heads,
+        _ => false,
+    };
+    if agree && deterministic {
+        for (i, tape) in tapes.iter().enumerate() {
+            println!("\tTape {}: {}", i + 1, tape);
+        }
+    }
+    Some(agree)
+}
+
+// *********************************************************************
+/// Short form of an outcome
+fn outcome_word(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Accept => "accepted".to_string(),
+        Outcome::Reject => "rejected".to_string(),
+        Outcome::NoHalt(_) => "still running".to_string(),
+        Outcome::NotFound(_) => "not found".to_string(),
+    }
+}
+
+// Test Functions
+#[test]
+fn test_single_tape_agrees() {
+    let tm = TM::new_from_file("tm2.yaml");
+    let single = SingleTape::new(&tm);
+    assert_eq!(single.machine.validate(), Ok(()));
+
+    for input in ["", "01", "0011", "001", "011", "10", "1"] {
+        let expected = tm.run(input, 100).0;
+        assert_eq!(single.machine.run(input, 10000).0, expected, "{}", input);
+        assert_eq!(compare(&tm, &single, input, 100), Some(true), "{}", input);
+    }
+
+    // cut off before it halts, the original is not compared with a single
+    // tape run given steps enough to finish
+    assert_eq!(tm.simulate("000111", 5).0, Outcome::NoHalt(5));
+    assert_eq!(compare(&tm, &single, "000111", 5), None);
+    assert_eq!(compare(&tm, &single, "01", 3), None);
+
+    // a nondeterministic machine gives a nondeterministic one
+    let tm = TM::new_from_file("ntm.yaml");
+    let single = SingleTape::new(&tm);
+    assert!(!single.machine.is_deterministic());
+    for input in ["101", "0110", "1100101"] {
+        assert_eq!(compare(&tm, &single, input, 100), Some(true), "{}", input);
+    }
+}
@@ -0,0 +1,617 @@
+//! Turing machines
+//!
+//! States are numbered from 1 like the other machines. A machine has one
+//! or more tapes; the first starts with the input at cell 0 and every
+//! other cell of every tape is blank. Tapes grow to the right as needed
+//! and a move left from cell 0 leaves the head where it is. A machine
+//! that has no transition for its state and symbols halts and rejects.
+//!
+//! A machine with several transitions for one state and symbols is
+//! nondeterministic; it accepts when some computation reaches an accept
+//! state, found by searching the configurations breadth-first.
+
+use std::collections::{HashSet, VecDeque};
+
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+// *********************************************************************
+/// # Direction the head moves after writing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Direction {
+    /// One cell to the left
+    L,
+
+    /// One cell to the right
+    R,
+
+    /// Stay on the same cell
+    S,
+}
+
+// *********************************************************************
+/// # A single transition `d(from, read) = (to, write, move)`
+///
+/// `read`, `write` and `move` have one entry per tape; in the YAML file a
+/// single-tape machine may give each as a plain value instead of a list.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Transition {
+    /// State number (1 relative) the transition leaves
+    pub from: usize,
+
+    /// Tape symbols under the heads
+    #[serde(deserialize_with = "one_or_many")]
+    pub read: Vec<char>,
+
+    /// State number (1 relative) the transition enters
+    pub to: usize,
+
+    /// Tape symbols written over the ones read
+    #[serde(deserialize_with = "one_or_many")]
+    pub write: Vec<char>,
+
+    /// Where each head goes next
+    #[serde(rename = "move", deserialize_with = "one_or_many")]
+    pub direction: Vec<Direction>,
+}
+
+// *********************************************************************
+/// # A YAML value given either on its own or as a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+// *********************************************************************
+/// # Turing Machine Structure
+///
+/// Create a structure that the YAML files will be deserialized into.
+#[derive(Debug, Deserialize)]
+pub struct TM {
+    /// Number of states, numbered from 1
+    pub states: usize,
+
+    /// Number of tapes
+    #[serde(default = "one_tape")]
+    pub tapes: usize,
+
+    /// Characters the input string may contain
+    pub input_alphabet: Vec<char>,
+
+    /// Characters that may be on the tape, including the blank
+    pub tape_alphabet: Vec<char>,
+
+    /// Character filling the unused tape
+    pub blank: char,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Set of reject states (1 relative)
+    #[serde(default)]
+    pub reject: Vec<usize>,
+
+    /// The transitions, several for one state and tape symbols make the
+    /// machine nondeterministic
+    pub transitions: Vec<Transition>,
+}
+
+// *********************************************************************
+/// # State, tapes and head positions of a running machine
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Configuration {
+    /// Current state (1 relative)
+    pub state: usize,
+
+    /// Contents of each tape from cell 0 to the last cell visited
+    pub tapes: Vec<Vec<char>>,
+
+    /// Cell under the head of each tape
+    pub heads: Vec<usize>,
+}
+
+// *********************************************************************
+/// # How a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Halted in an accept state
+    Accept,
+
+    /// Halted in a reject state or with no transition to take
+    Reject,
+
+    /// Still running after the given number of steps
+    NoHalt(usize),
+
+    /// No accepting computation among the given number of configurations
+    /// of a nondeterministic machine
+    NotFound(usize),
+}
+
+// *********************************************************************
+/// Implement the methods of the TM structure
+impl TM {
+    /// Create and return a TM on the heap
+    ///
+    /// Load the .yaml file specified into a TM structure
+    /// on the heap and return a point to it via a Box.
+    pub fn new_from_file(filename: &str) -> Box<TM> {
+        let f = std::fs::File::open(filename).expect("Unable to open input");
+
+        // Deserialize into the heap and return the pointer
+        Box::new(serde_yaml::from_reader(f).expect("Unable to parse yaml"))
+    }
+
+    /// Validate the correctness of the TM
+    pub fn validate(&self) -> Result<(), String> {
+        // The start, accept and reject states must be valid
+        if self.start == 0 || self.start > self.states {
+            return Err(format!("Start state({}), is not valid", self.start));
+        }
+        for state in self.accept.iter().chain(self.reject.iter()) {
+            if *state == 0 || *state > self.states {
+                return Err(format!("Halting state({}), is not valid", state));
+            }
+        }
+        if let Some(state) = self.accept.iter().find(|s| self.reject.contains(s)) {
+            return Err(format!("State({}) both accepts and rejects", state));
+        }
+
+        // The blank is on the tape but never in the input
+        if !self.tape_alphabet.contains(&self.blank) {
+            return Err(format!(
+                "Blank '{}' is not in the tape alphabet",
+                self.blank
+            ));
+        }
+        if self.input_alphabet.contains(&self.blank) {
+            return Err(format!("Blank '{}' is in the input alphabet", self.blank));
+        }
+        if let Some(c) = self
+            .input_alphabet
+            .iter()
+            .find(|c| !self.tape_alphabet.contains(c))
+        {
+            return Err(format!(
+                "Input character '{}' is not in the tape alphabet",
+                c
+            ));
+        }
+
+        if self.tapes == 0 {
+            return Err("A machine needs at least one tape".to_string());
+        }
+
+        // Validate every transition against the states and tape alphabet
+        for (n, t) in self.transitions.iter().enumerate() {
+            if t.read.len() != self.tapes
+                || t.write.len() != self.tapes
+                || t.direction.len() != self.tapes
+            {
+                return Err(format!(
+                    "Transition {} should have {} symbols read, written and moves: {}",
+                    n + 1,
+                    self.tapes,
+                    t
+                ));
+            }
+            if t.from == 0 || t.from > self.states || t.to == 0 || t.to > self.states {
+                return Err(format!("Invalid state in transition {}: {}", n + 1, t));
+            }
+            if t.read
+                .iter()
+                .chain(t.write.iter())
+                .any(|c| !self.tape_alphabet.contains(c))
+            {
+                return Err(format!(
+                    "Transition {} uses a character not in the tape alphabet: {}",
+                    n + 1,
+                    t
+                ));
+            }
+            if self.is_halting(t.from) {
+                return Err(format!(
+                    "Transition {} leaves a halting state: {}",
+                    n + 1,
+                    t
+                ));
+            }
+            if self.transitions[..n].contains(t) {
+                return Err(format!("Transition {} is listed twice: {}", n + 1, t));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Is there at most one transition for every state and tape symbols
+    pub fn is_deterministic(&self) -> bool {
+        self.transitions.iter().enumerate().all(|(n, t)| {
+            !self.transitions[..n]
+                .iter()
+                .any(|o| o.from == t.from && o.read == t.read)
+        })
+    }
+
+    /// Is the state an accept or reject state
+    pub fn is_halting(&self, state: usize) -> bool {
+        self.accept.contains(&state) || self.reject.contains(&state)
+    }
+
+    /// The configuration before the first step on `input`
+    pub fn initial(&self, input: &str) -> Configuration {
+        let mut tapes = vec![vec![self.blank]; self.tapes];
+        if !input.is_empty() {
+            tapes[0] = input.chars().collect();
+        }
+        Configuration {
+            state: self.start,
+            tapes,
+            heads: vec![0; self.tapes],
+        }
+    }
+
+    /// Take one step, returning the transition used and the configuration
+    /// after it, or None when the machine halts
+    ///
+    /// A nondeterministic machine takes its first applicable transition.
+    pub fn step(&self, config: &Configuration) -> Option<(&Transition, Configuration)> {
+        self.successors(config).into_iter().next()
+    }
+
+    /// Every transition that applies to `config` with the configuration
+    /// it leads to
+    pub fn successors(&self, config: &Configuration) -> Vec<(&Transition, Configuration)> {
+        if self.is_halting(config.state) {
+            return Vec::new();
+        }
+        let symbols = config.symbols();
+        self.transitions
+            .iter()
+            .filter(|t| t.from == config.state && t.read == symbols)
+            .map(|t| (t, self.apply(t, config)))
+            .collect()
+    }
+
+    /// The configuration after taking `t` from `config`
+    fn apply(&self, t: &Transition, config: &Configuration) -> Configuration {
+        let mut next = config.clone();
+        next.state = t.to;
+        for (i, tape) in next.tapes.iter_mut().enumerate() {
+            let head = &mut next.heads[i];
+            tape[*head] = t.write[i];
+            match t.direction[i] {
+                Direction::L => *head = head.saturating_sub(1),
+                Direction::R => {
+                    *head += 1;
+                    if *head == tape.len() {
+                        tape.push(self.blank);
+                    }
+                }
+                Direction::S => (),
+            }
+        }
+        next
+    }
+
+    /// Run on `input` for at most `limit` steps, returning how the run
+    /// ended and every configuration it went through
+    pub fn run(&self, input: &str, limit: usize) -> (Outcome, Vec<Configuration>) {
+        let mut trace = vec![self.initial(input)];
+        while trace.len() <= limit {
+            match self.step(trace.last().unwrap()) {
+                Some((_, next)) => trace.push(next),
+                None => {
+                    let state = trace.last().unwrap().state;
+                    let outcome = if self.accept.contains(&state) {
+                        Outcome::Accept
+                    } else {
+                        Outcome::Reject
+                    };
+                    return (outcome, trace);
+                }
+            }
+        }
+        (Outcome::NoHalt(limit), trace)
+    }
+
+    /// Search the computations on `input` breadth-first, keeping no more
+    /// than `budget` distinct configurations
+    ///
+    /// Returns the first accepting computation found, which is a shortest
+    /// one. Each configuration is checked as it is reached, and once the
+    /// budget is spent the successors of the configurations already kept
+    /// are still checked before giving up. A rejection means every
+    /// computation halts without accepting; otherwise only the starting
+    /// configuration is returned.
+    pub fn search(&self, input: &str, budget: usize) -> (Outcome, Vec<Configuration>) {
+        let start = self.initial(input);
+        let mut nodes: Vec<(Configuration, Option<usize>)> = vec![(start.clone(), None)];
+        let path_to = |nodes: &[(Configuration, Option<usize>)], n: usize| {
+            let mut path = Vec::new();
+            let mut current = Some(n);
+            while let Some(i) = current {
+                path.push(nodes[i].0.clone());
+                current = nodes[i].1;
+            }
+            path.reverse();
+            path
+        };
+        if self.accept.contains(&start.state) {
+            return (Outcome::Accept, vec![start]);
+        }
+
+        let mut seen: HashSet<Configuration> = HashSet::new();
+        seen.insert(start.clone());
+        let mut queue = VecDeque::new();
+        queue.push_back(0);
+        let mut spent = false;
+        while let Some(n) = queue.pop_front() {
+            for (_, next) in self.successors(&nodes[n].0) {
+                if seen.contains(&next) {
+                    continue;
+                }
+                if self.accept.contains(&next.state) {
+                    let mut path = path_to(&nodes, n);
+                    path.push(next);
+                    return (Outcome::Accept, path);
+                }
+                if nodes.len() >= budget {
+                    spent = true;
+                    continue;
+                }
+                seen.insert(next.clone());
+                nodes.push((next, Some(n)));
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+        if spent {
+            (Outcome::NotFound(budget), vec![start])
+        } else {
+            (Outcome::Reject, vec![start])
+        }
+    }
+
+    /// Run a deterministic machine for at most `limit` steps or search a
+    /// nondeterministic one through at most `limit` configurations
+    pub fn simulate(&self, input: &str, limit: usize) -> (Outcome, Vec<Configuration>) {
+        if self.is_deterministic() {
+            self.run(input, limit)
+        } else {
+            self.search(input, limit)
+        }
+    }
+
+    /// Print every step of a run with the tape and a head marker
+    pub fn print_trace(&self, trace: &[Configuration]) {
+        println!("Transition steps:");
+        for (n, config) in trace.iter().enumerate() {
+            if n > 0 {
+                let (t, _) = self
+                    .successors(&trace[n - 1])
+                    .into_iter()
+                    .find(|(_, next)| next == config)
+                    .unwrap();
+                println!("{}", t);
+            }
+            println!("{}", config);
+        }
+    }
+
+    /// Write the state diagram to stdout
+    pub fn write_graphviz(&self) {
+        self.write_graphviz_with(|c| c.to_string());
+    }
+
+    /// Write the state diagram to stdout, showing tape symbols with `name`
+    ///
+    /// Transitions between the same two states share one edge. An edge
+    /// with more than four of them lists three and how many more there
+    /// are, so machines built by a conversion stay readable.
+    pub fn write_graphviz_with(&self, name: impl Fn(char) -> String) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape=doublecircle]; q{};", state);
+        }
+        for state in &self.reject {
+            println!("\tnode [shape=octagon]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+        self.edges(name).write_with("\\n", Some(4), "");
+        println!("}}");
+    }
+
+    /// Write the state diagram to stdout as a Mermaid state diagram,
+    /// reject states described as such
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.accept = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        for state in &self.reject {
+            diagram
+                .descriptions
+                .push((format!("q{}", state), "reject".to_string()));
+        }
+        diagram.write(&self.edges(|c| c.to_string()));
+    }
+
+    /// One edge per pair of states, labelled with every transition and
+    /// tape symbols shown with `name`
+    fn edges(&self, name: impl Fn(char) -> String) -> Edges {
+        let mut edges = Edges::new();
+        for t in &self.transitions {
+            let label = format!(
+                "{} -> {}, {}",
+                group(&t.read, |c| name(*c)),
+                group(&t.write, |c| name(*c)),
+                group(&t.direction, |d| format!("{:?}", d))
+            );
+            edges.add(&format!("q{}", t.from), &format!("q{}", t.to), &label);
+        }
+        edges
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Configuration structure
+impl Configuration {
+    /// The symbols under the heads
+    pub fn symbols(&self) -> Vec<char> {
+        self.tapes
+            .iter()
+            .zip(self.heads.iter())
+            .map(|(tape, head)| tape[*head])
+            .collect()
+    }
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |items: Vec<String>| items.join(", ");
+        write!(
+            f,
+            "d(q{}, {}) -> (q{}, {}, {})",
+            self.from,
+            list(self.read.iter().map(|c| c.to_string()).collect()),
+            self.to,
+            list(self.write.iter().map(|c| c.to_string()).collect()),
+            list(self.direction.iter().map(|d| format!("{:?}", d)).collect())
+        )
+    }
+}
+
+impl fmt::Display for Configuration {
+    /// Each tape with `^` under its head cell and the state in front of
+    /// the first one
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = format!("q{}: ", self.state);
+        let width = prefix.chars().count();
+        for (i, tape) in self.tapes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let tape: String = tape.iter().collect();
+            let front = if i == 0 { prefix.as_str() } else { "" };
+            write!(
+                f,
+                "\t{:w$}{}\n\t{:h$}^",
+                front,
+                tape,
+                "",
+                w = width,
+                h = width + self.heads[i]
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Accept => write!(f, "The string is accepted by the machine."),
+            Outcome::Reject => write!(f, "The string is rejected by the machine."),
+            Outcome::NoHalt(n) => write!(f, "The machine did not halt within {} steps.", n),
+            Outcome::NotFound(n) => write!(
+                f,
+                "No accepting computation was found within {} configurations.",
+                n
+            ),
+        }
+    }
+}
+
+// *********************************************************************
+/// Deserialize a plain value as a list of one
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+// *********************************************************************
+/// Machines have one tape unless the file says otherwise
+fn one_tape() -> usize {
+    1
+}
+
+// *********************************************************************
+/// One item on its own, several as `(a, b)`
+fn group<T>(items: &[T], show: impl Fn(&T) -> String) -> String {
+    let shown: Vec<String> = items.iter().map(show).collect();
+    if shown.len() == 1 {
+        shown[0].clone()
+    } else {
+        format!("({})", shown.join(","))
+    }
+}
+
+// Test Functions
+#[test]
+fn test_tm_runs() {
+    let tm = TM::new_from_file("tm.yaml");
+    assert_eq!(tm.validate(), Ok(()));
+    assert_eq!(tm.run("", 100).0, Outcome::Accept);
+    assert_eq!(tm.run("0011", 100).0, Outcome::Accept);
+    assert_eq!(tm.run("001", 100).0, Outcome::Reject);
+    assert_eq!(tm.run("10", 100).0, Outcome::Reject);
+
+    let (outcome, trace) = tm.run("0011", 3);
+    assert_eq!(outcome, Outcome::NoHalt(3));
+    assert_eq!(trace.len(), 4);
+    assert_eq!(trace[3].to_string(), "\tq3: x0y1\n\t     ^");
+
+    // a second transition for q1 reading 0 makes it nondeterministic
+    let mut tm = tm;
+    let mut extra = tm.transitions[0].clone();
+    assert!(tm.is_deterministic());
+    extra.to = 3;
+    tm.transitions.push(extra.clone());
+    assert_eq!(tm.validate(), Ok(()));
+    assert!(!tm.is_deterministic());
+    tm.transitions.push(extra);
+    assert!(tm.validate().is_err());
+
+    // guessing where 101 starts
+    let tm = TM::new_from_file("ntm.yaml");
+    assert_eq!(tm.validate(), Ok(()));
+    let (outcome, path) = tm.search("0110101", 100);
+    assert_eq!(outcome, Outcome::Accept);
+    assert_eq!(path.len(), 6);
+    assert_eq!(path[3].state, 2);
+    assert_eq!(tm.search("0110", 100).0, Outcome::Reject);
+    assert_eq!(tm.search("0110101", 5).0, Outcome::NotFound(5));
+    // the starting configuration alone is over a budget of 0
+    assert_eq!(tm.search("0110101", 0).0, Outcome::NotFound(0));
+
+    // the accepting configuration is reached from the last one kept
+    let (outcome, path) = tm.search("101", 5);
+    assert_eq!(outcome, Outcome::Accept);
+    assert_eq!(path.len(), 4);
+    assert_eq!(tm.search("101", 3).0, Outcome::NotFound(3));
+
+    // the copy of the 0s on the second tape is matched against the 1s
+    let tm = TM::new_from_file("tm2.yaml");
+    assert_eq!(tm.validate(), Ok(()));
+    assert_eq!(tm.run("000111", 100).0, Outcome::Accept);
+    assert_eq!(tm.run("0001", 100).0, Outcome::Reject);
+    let (_, trace) = tm.run("01", 2);
+    assert_eq!(
+        trace[2].to_string(),
+        "\tq2: 01\n\t     ^\n\t    $0_\n\t      ^"
+    );
+}
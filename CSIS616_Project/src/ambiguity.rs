@@ -0,0 +1,139 @@
+//! Bounded search for ambiguous strings
+//!
+//! Strings are tried shortest first, only extending prefixes that the
+//! Earley chart says can still lead to a string of the grammar. Finding
+//! nothing only means no string up to the length bound is ambiguous.
+
+use crate::earley::Earley;
+use crate::grammar::{Grammar, ParseTree};
+
+// *********************************************************************
+/// # A string with two different parse trees
+#[derive(Debug)]
+pub struct Ambiguity {
+    /// The ambiguous string
+    pub input: String,
+
+    /// Two distinct parse trees of the string
+    pub trees: [ParseTree; 2],
+}
+
+// *********************************************************************
+/// Implement the methods of the Ambiguity structure
+impl Ambiguity {
+    /// Print both trees as one Graphviz digraph, side by side
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\tordering=out;");
+        println!("\tnode [shape=circle];");
+        for (i, tree) in self.trees.iter().enumerate() {
+            println!("\tsubgraph cluster_{} {{", i);
+            println!("\t\tlabel=\"Tree {}\";", i + 1);
+            for line in tree.graphviz_lines(&format!("t{}_", i)) {
+                println!("\t\t{}", line);
+            }
+            println!("\t}}");
+        }
+        println!("}}");
+    }
+}
+
+// *********************************************************************
+/// Find the shortest string of at most `max_length` characters with two
+/// parse trees
+pub fn find_ambiguous(grammar: &Grammar, max_length: usize) -> Option<Ambiguity> {
+    let terminals = grammar.terminals();
+    let mut level = vec![String::new()];
+
+    for length in 0..=max_length {
+        let mut next = Vec::new();
+        for input in &level {
+            let earley = Earley::new(grammar, input);
+            let mut trees = earley.parse_trees(2);
+            if trees.len() == 2 {
+                let second = trees.pop().unwrap();
+                let first = trees.pop().unwrap();
+                return Some(Ambiguity {
+                    input: input.clone(),
+                    trees: [first, second],
+                });
+            }
+            if length < max_length && earley.viable_prefix() {
+                for c in &terminals {
+                    next.push(format!("{}{}", input, c));
+                }
+            }
+        }
+        level = next;
+    }
+
+    None
+}
+
+// *********************************************************************
+/// The sentential forms of the leftmost derivation a parse tree stands for
+pub fn leftmost_derivation(tree: &ParseTree) -> Vec<String> {
+    let mut form: Vec<&ParseTree> = vec![tree];
+    let mut steps = vec![form_string(&form)];
+
+    // expand the leftmost node that has children until only leaves remain
+    while let Some(i) = form.iter().position(|t| !t.children.is_empty()) {
+        let node = form.remove(i);
+        let children = node.children.iter().filter(|c| !is_epsilon(c));
+        for (k, child) in children.enumerate() {
+            form.insert(i + k, child);
+        }
+        steps.push(form_string(&form));
+    }
+
+    steps
+}
+
+// *********************************************************************
+/// Is the tree the ε leaf of an empty body
+fn is_epsilon(tree: &ParseTree) -> bool {
+    tree.children.is_empty() && tree.label == "ε"
+}
+
+// *********************************************************************
+/// Printable form of a sentential form
+fn form_string(form: &[&ParseTree]) -> String {
+    if form.is_empty() {
+        "ε".to_string()
+    } else {
+        let labels: Vec<&str> = form.iter().map(|t| t.label.as_str()).collect();
+        labels.join(" ")
+    }
+}
+
+// Test Functions
+#[test]
+fn test_find_ambiguous() {
+    let g = Grammar::parse("E -> E + E | a").unwrap();
+    assert!(find_ambiguous(&g, 4).is_none());
+
+    let found = find_ambiguous(&g, 8).unwrap();
+    assert_eq!(found.input, "a+a+a");
+    assert_ne!(found.trees[0], found.trees[1]);
+    let derivation = leftmost_derivation(&found.trees[0]);
+    assert_eq!(derivation[0], "E");
+    assert_eq!(derivation[1], "E + E");
+    assert_eq!(derivation.last().unwrap(), "a + a + a");
+
+    let g = Grammar::parse("S -> a S b | ε").unwrap();
+    assert!(find_ambiguous(&g, 6).is_none());
+    assert_eq!(
+        leftmost_derivation(&Earley::new(&g, "ab").parse_trees(1)[0]),
+        vec!["S", "a S b", "a b"]
+    );
+
+    // S -> S S derives ε a second way, through the ε cycle
+    let g = Grammar::parse("S -> a S b | S S | ε").unwrap();
+    let found = find_ambiguous(&g, 5).unwrap();
+    assert_eq!(found.input, "");
+    assert_ne!(found.trees[0], found.trees[1]);
+
+    // and S -> S derives a a second way, through the unit cycle
+    let g = Grammar::parse("S -> S | a").unwrap();
+    assert_eq!(find_ambiguous(&g, 3).unwrap().input, "a");
+}
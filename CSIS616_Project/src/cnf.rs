@@ -0,0 +1,318 @@
+//! Chomsky normal form and CYK membership
+//!
+//! Every production of a grammar in Chomsky normal form is `A -> B C` or
+//! `A -> a`, with `S -> ε` allowed for a start symbol that never appears
+//! on a right side.
+
+use crate::grammar::{
+    fresh_name, nonterminal_name, push_unique, Grammar, ParseTree, Production, Symbol,
+};
+use std::collections::{BTreeMap, HashMap};
+
+// *********************************************************************
+/// # How a nonterminal in a CYK cell derives its substring
+#[derive(Debug, Clone)]
+enum Derivation {
+    /// `A -> a`
+    Terminal(char),
+
+    /// `A -> B C` with `B` deriving the first `usize` characters
+    Split(usize, String, String),
+}
+
+// *********************************************************************
+/// # CYK dynamic programming table
+#[derive(Debug)]
+pub struct Cyk {
+    /// The string being checked
+    input: Vec<char>,
+
+    /// Start symbol of the grammar
+    start: String,
+
+    /// Does the grammar have `S -> ε`
+    accepts_empty: bool,
+
+    /// `table[len - 1][i]` holds the nonterminals deriving
+    /// `input[i..i + len]`, each with the first derivation found
+    table: Vec<Vec<BTreeMap<String, Derivation>>>,
+}
+
+// *********************************************************************
+/// Convert a grammar to Chomsky normal form
+///
+/// The steps are the textbook ones: a new start symbol, removal of
+/// ε-productions, unit productions and useless symbols, then terminals
+/// on long right sides replaced by new nonterminals and long right sides
+/// split into pairs.
+pub fn to_cnf(grammar: &Grammar) -> Grammar {
+    let mut g = grammar.clone();
+
+    // START: the start symbol must not appear on a right side
+    if g.start_on_right() {
+        let new_start = g.fresh_nonterminal('S');
+        g.productions.insert(
+            0,
+            Production {
+                head: new_start.clone(),
+                body: vec![Symbol::Nonterminal(g.start.clone())],
+            },
+        );
+        g.start = new_start;
+    }
+
+    // DEL, UNIT and useless symbols
+    let mut g = g.simplify();
+
+    // names already used, extended as new nonterminals are created
+    let mut used = g.nonterminals();
+
+    // TERM: A -> ... a ... becomes A -> ... T0 ... with T0 -> a
+    let mut term_names: BTreeMap<char, String> = BTreeMap::new();
+    let mut productions = Vec::new();
+    for p in &g.productions {
+        if p.body.len() < 2 {
+            productions.push(p.clone());
+            continue;
+        }
+        let body = p
+            .body
+            .iter()
+            .map(|symbol| match symbol {
+                Symbol::Terminal(c) => Symbol::Nonterminal(
+                    term_names
+                        .entry(*c)
+                        .or_insert_with(|| fresh_name(&mut used, 'T'))
+                        .clone(),
+                ),
+                nonterminal => nonterminal.clone(),
+            })
+            .collect();
+        productions.push(Production {
+            head: p.head.clone(),
+            body,
+        });
+    }
+    for (c, name) in &term_names {
+        productions.push(Production {
+            head: name.clone(),
+            body: vec![Symbol::Terminal(*c)],
+        });
+    }
+
+    // BIN: A -> B1 B2 ... Bk becomes A -> B1 X0, X0 -> B2 X1, ...
+    // with one new nonterminal per distinct tail
+    let mut tail_names: HashMap<Vec<Symbol>, String> = HashMap::new();
+    g.productions = Vec::new();
+    for p in productions {
+        let mut head = p.head;
+        let mut body = p.body;
+        loop {
+            if body.len() <= 2 {
+                push_unique(&mut g.productions, Production { head, body });
+                break;
+            }
+            let tail = body.split_off(1);
+            let existing = tail_names.get(&tail).cloned();
+            let rest = match &existing {
+                Some(name) => name.clone(),
+                None => {
+                    let name = fresh_name(&mut used, 'X');
+                    tail_names.insert(tail.clone(), name.clone());
+                    name
+                }
+            };
+            body.push(Symbol::Nonterminal(rest.clone()));
+            push_unique(&mut g.productions, Production { head, body });
+
+            // the productions for an existing tail are already present
+            if existing.is_some() {
+                break;
+            }
+            head = rest;
+            body = tail;
+        }
+    }
+
+    g
+}
+
+// *********************************************************************
+/// Is the grammar in Chomsky normal form
+pub fn is_cnf(grammar: &Grammar) -> bool {
+    let start_on_right = grammar.start_on_right();
+    grammar.productions.iter().all(|p| match p.body.as_slice() {
+        [] => p.head == grammar.start && !start_on_right,
+        [Symbol::Terminal(_)] => true,
+        [Symbol::Nonterminal(_), Symbol::Nonterminal(_)] => true,
+        _ => false,
+    })
+}
+
+// *********************************************************************
+/// Implement the methods of the Cyk structure
+impl Cyk {
+    /// Fill the CYK table for `input` using a grammar in Chomsky normal form
+    pub fn new(grammar: &Grammar, input: &str) -> Cyk {
+        let input: Vec<char> = input.chars().collect();
+        let n = input.len();
+        let mut table: Vec<Vec<BTreeMap<String, Derivation>>> = Vec::new();
+
+        // substrings of length 1
+        table.push(
+            input
+                .iter()
+                .map(|c| {
+                    let mut cell = BTreeMap::new();
+                    for p in &grammar.productions {
+                        if p.body == [Symbol::Terminal(*c)] {
+                            cell.entry(p.head.clone())
+                                .or_insert(Derivation::Terminal(*c));
+                        }
+                    }
+                    cell
+                })
+                .collect(),
+        );
+
+        // longer substrings from every split into two shorter ones
+        for len in 2..=n {
+            let mut row = Vec::new();
+            for i in 0..=n - len {
+                let mut cell = BTreeMap::new();
+                for split in 1..len {
+                    let left = &table[split - 1][i];
+                    let right = &table[len - split - 1][i + split];
+                    for p in &grammar.productions {
+                        if let [Symbol::Nonterminal(b), Symbol::Nonterminal(c)] = p.body.as_slice()
+                        {
+                            if left.contains_key(b) && right.contains_key(c) {
+                                cell.entry(p.head.clone()).or_insert_with(|| {
+                                    Derivation::Split(split, b.clone(), c.clone())
+                                });
+                            }
+                        }
+                    }
+                }
+                row.push(cell);
+            }
+            table.push(row);
+        }
+
+        Cyk {
+            input,
+            start: grammar.start.clone(),
+            accepts_empty: grammar
+                .productions_of(&grammar.start)
+                .any(|p| p.body.is_empty()),
+            table,
+        }
+    }
+
+    /// Does the start symbol derive the whole input
+    pub fn accepted(&self) -> bool {
+        if self.input.is_empty() {
+            self.accepts_empty
+        } else {
+            self.table[self.input.len() - 1][0].contains_key(&self.start)
+        }
+    }
+
+    /// Print the table with the longest substrings on top and the input
+    /// characters along the bottom
+    pub fn print_table(&self) {
+        let cells: Vec<Vec<String>> = self
+            .table
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        if cell.is_empty() {
+                            "-".to_string()
+                        } else {
+                            let names: Vec<String> =
+                                cell.keys().map(|k| nonterminal_name(k)).collect();
+                            names.join(",")
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = cells
+            .iter()
+            .flatten()
+            .map(|c| c.chars().count())
+            .max()
+            .unwrap_or(1);
+
+        println!("CYK table:");
+        for (len, row) in cells.iter().enumerate().rev() {
+            let row: Vec<String> = row
+                .iter()
+                .map(|c| format!("{:<w$}", c, w = width))
+                .collect();
+            println!(
+                "{}",
+                format!("{:>3} | {}", len + 1, row.join(" | ")).trim_end()
+            );
+        }
+        let chars: Vec<String> = self
+            .input
+            .iter()
+            .map(|c| format!("{:<w$}", c, w = width))
+            .collect();
+        println!("{}", format!("    | {}", chars.join(" | ")).trim_end());
+    }
+
+    /// Parse tree for an accepted string
+    pub fn parse_tree(&self) -> Option<ParseTree> {
+        if !self.accepted() {
+            return None;
+        }
+        if self.input.is_empty() {
+            return Some(ParseTree {
+                label: self.start.clone(),
+                children: vec![ParseTree::leaf("ε")],
+            });
+        }
+        Some(self.subtree(&self.start, 0, self.input.len()))
+    }
+
+    /// Tree for `name` deriving `input[i..i + len]`
+    fn subtree(&self, name: &str, i: usize, len: usize) -> ParseTree {
+        let children = match &self.table[len - 1][i][name] {
+            Derivation::Terminal(c) => vec![ParseTree::leaf(&c.to_string())],
+            Derivation::Split(split, b, c) => vec![
+                self.subtree(b, i, *split),
+                self.subtree(c, i + split, len - split),
+            ],
+        };
+        ParseTree {
+            label: name.to_string(),
+            children,
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_cnf_and_cyk() {
+    let g = Grammar::parse("S -> a S b | ε").unwrap();
+    let cnf = to_cnf(&g);
+    assert!(is_cnf(&cnf));
+
+    for (s, accept) in [
+        ("", true),
+        ("ab", true),
+        ("aabb", true),
+        ("aab", false),
+        ("ba", false),
+    ] {
+        let cyk = Cyk::new(&cnf, s);
+        assert_eq!(cyk.accepted(), accept, "{}", s);
+        assert_eq!(cyk.parse_tree().is_some(), accept);
+    }
+
+    let tree = Cyk::new(&cnf, "ab").parse_tree().unwrap();
+    assert_eq!(tree.to_string(), "S0(T0(a) T1(b))");
+}
@@ -0,0 +1,338 @@
+//! Earley parsing
+//!
+//! Works directly on any context free grammar, including ambiguous and
+//! left recursive ones. ε-productions are handled by advancing over
+//! nullable nonterminals when they are predicted (Aycock and Horspool).
+
+use crate::grammar::{nonterminal_name, Grammar, ParseTree, Symbol};
+use std::collections::HashSet;
+
+// *********************************************************************
+/// # Earley item `A -> α • β, origin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    /// Index of the production in the grammar
+    production: usize,
+
+    /// Number of body symbols already matched
+    dot: usize,
+
+    /// Input position where the item was predicted
+    origin: usize,
+}
+
+// *********************************************************************
+/// # Earley chart for one input string
+#[derive(Debug)]
+pub struct Earley {
+    /// Grammar being parsed with
+    grammar: Grammar,
+
+    /// The string being parsed
+    input: Vec<char>,
+
+    /// `chart[i]` holds the items after reading `i` characters
+    chart: Vec<Vec<Item>>,
+
+    /// `(head, from, to)` for every nonterminal deriving `input[from..to]`
+    completed: HashSet<(String, usize, usize)>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Earley structure
+impl Earley {
+    /// Fill the chart for `input`
+    pub fn new(grammar: &Grammar, input: &str) -> Earley {
+        let input: Vec<char> = input.chars().collect();
+        let nullable = grammar.nullable();
+        let mut chart: Vec<Vec<Item>> = vec![Vec::new(); input.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); input.len() + 1];
+
+        for (n, p) in grammar.productions.iter().enumerate() {
+            if p.head == grammar.start {
+                add_item(
+                    &mut chart,
+                    &mut seen,
+                    0,
+                    Item {
+                        production: n,
+                        dot: 0,
+                        origin: 0,
+                    },
+                );
+            }
+        }
+
+        for i in 0..=input.len() {
+            let mut j = 0;
+            while j < chart[i].len() {
+                let item = chart[i][j];
+                let production = &grammar.productions[item.production];
+
+                match production.body.get(item.dot) {
+                    None => {
+                        // complete: advance every item waiting on this head
+                        for k in 0..chart[item.origin].len() {
+                            let waiting = chart[item.origin][k];
+                            let body = &grammar.productions[waiting.production].body;
+                            if matches!(body.get(waiting.dot), Some(s) if s.is_nonterminal(&production.head))
+                            {
+                                add_item(&mut chart, &mut seen, i, advance(waiting));
+                            }
+                        }
+                    }
+                    Some(Symbol::Nonterminal(name)) => {
+                        // predict
+                        for (n, p) in grammar.productions.iter().enumerate() {
+                            if &p.head == name {
+                                add_item(
+                                    &mut chart,
+                                    &mut seen,
+                                    i,
+                                    Item {
+                                        production: n,
+                                        dot: 0,
+                                        origin: i,
+                                    },
+                                );
+                            }
+                        }
+                        if nullable.contains(name) {
+                            add_item(&mut chart, &mut seen, i, advance(item));
+                        }
+                    }
+                    Some(Symbol::Terminal(c)) => {
+                        // scan
+                        if input.get(i) == Some(c) {
+                            add_item(&mut chart, &mut seen, i + 1, advance(item));
+                        }
+                    }
+                }
+                j += 1;
+            }
+        }
+
+        let mut completed = HashSet::new();
+        for (end, items) in chart.iter().enumerate() {
+            for item in items {
+                let p = &grammar.productions[item.production];
+                if item.dot == p.body.len() {
+                    completed.insert((p.head.clone(), item.origin, end));
+                }
+            }
+        }
+
+        Earley {
+            grammar: grammar.clone(),
+            input,
+            chart,
+            completed,
+        }
+    }
+
+    /// Does the start symbol derive the whole input
+    pub fn accepted(&self) -> bool {
+        self.completed
+            .contains(&(self.grammar.start.clone(), 0, self.input.len()))
+    }
+
+    /// Could more input still make a string of the grammar
+    ///
+    /// True when some item in the last chart set read the last character.
+    pub fn viable_prefix(&self) -> bool {
+        !self.chart[self.input.len()].is_empty()
+    }
+
+    /// Print every chart set with its items
+    pub fn print_chart(&self) {
+        println!("Earley chart:");
+        for (i, items) in self.chart.iter().enumerate() {
+            let read: String = self.input[..i].iter().collect();
+            println!("S({}) after \"{}\":", i, read);
+            for item in items {
+                let p = &self.grammar.productions[item.production];
+                let mut symbols: Vec<String> = p.body.iter().map(|s| s.to_string()).collect();
+                symbols.insert(item.dot, "•".to_string());
+                println!(
+                    "\t{} -> {}, {}",
+                    nonterminal_name(&p.head),
+                    symbols.join(" "),
+                    item.origin
+                );
+            }
+        }
+    }
+
+    /// Up to `limit` distinct parse trees of the whole input
+    ///
+    /// A derivation may go once around a cycle of unit or ε-productions
+    /// back to the same nonterminal and span, so a grammar like
+    /// `S -> S | a` shows its second tree. Inside that round cycles are
+    /// cut off, so no tree repeats a nonterminal on a span more than
+    /// twice, and the number of trees stays finite even before `limit`.
+    pub fn parse_trees(&self, limit: usize) -> Vec<ParseTree> {
+        if !self.accepted() || limit == 0 {
+            return Vec::new();
+        }
+        self.trees(
+            &self.grammar.start,
+            0,
+            self.input.len(),
+            limit,
+            &mut Vec::new(),
+            true,
+        )
+    }
+
+    /// Trees for `head` deriving `input[from..to]`
+    ///
+    /// A unit or ε cycle brings `head` back on the same span. With
+    /// `cycles` set, going once round the cycle is another derivation,
+    /// made from the trees of the span that do not go round it again;
+    /// without it the cycle is cut off.
+    fn trees(
+        &self,
+        head: &str,
+        from: usize,
+        to: usize,
+        limit: usize,
+        active: &mut Vec<(String, usize, usize)>,
+        cycles: bool,
+    ) -> Vec<ParseTree> {
+        let key = (head.to_string(), from, to);
+        if active.contains(&key) {
+            if cycles {
+                return self.trees(head, from, to, limit, &mut Vec::new(), false);
+            }
+            return Vec::new();
+        }
+        active.push(key);
+
+        let mut result = Vec::new();
+        for p in self.grammar.productions_of(head) {
+            let remaining = limit - result.len();
+            for children in self.sequences(&p.body, from, to, remaining, active, cycles) {
+                let children = if children.is_empty() {
+                    vec![ParseTree::leaf("ε")]
+                } else {
+                    children
+                };
+                result.push(ParseTree {
+                    label: nonterminal_name(head),
+                    children,
+                });
+            }
+            if result.len() >= limit {
+                break;
+            }
+        }
+
+        active.pop();
+        result
+    }
+
+    /// Subtrees for `body` deriving `input[from..to]`, one vector per way
+    fn sequences(
+        &self,
+        body: &[Symbol],
+        from: usize,
+        to: usize,
+        limit: usize,
+        active: &mut Vec<(String, usize, usize)>,
+        cycles: bool,
+    ) -> Vec<Vec<ParseTree>> {
+        match body.split_first() {
+            None => {
+                if from == to {
+                    vec![vec![]]
+                } else {
+                    vec![]
+                }
+            }
+            Some((Symbol::Terminal(c), rest)) => {
+                if from < to && self.input[from] == *c {
+                    self.sequences(rest, from + 1, to, limit, active, cycles)
+                        .into_iter()
+                        .map(|tail| prepend(ParseTree::leaf(&c.to_string()), tail))
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+            Some((Symbol::Nonterminal(name), rest)) => {
+                let mut result = Vec::new();
+                for mid in from..=to {
+                    if !self.completed.contains(&(name.clone(), from, mid)) {
+                        continue;
+                    }
+                    let tails = self.sequences(rest, mid, to, limit, active, cycles);
+                    if tails.is_empty() {
+                        continue;
+                    }
+                    for first in self.trees(name, from, mid, limit, active, cycles) {
+                        for tail in &tails {
+                            result.push(prepend(first.clone(), tail.clone()));
+                            if result.len() >= limit {
+                                return result;
+                            }
+                        }
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+// *********************************************************************
+/// Add an item to a chart set unless it is already there
+fn add_item(chart: &mut [Vec<Item>], seen: &mut [HashSet<Item>], i: usize, item: Item) {
+    if seen[i].insert(item) {
+        chart[i].push(item);
+    }
+}
+
+// *********************************************************************
+/// Move the dot of an item over one symbol
+fn advance(item: Item) -> Item {
+    Item {
+        dot: item.dot + 1,
+        ..item
+    }
+}
+
+// *********************************************************************
+/// Put a tree in front of a list of trees
+fn prepend(first: ParseTree, mut rest: Vec<ParseTree>) -> Vec<ParseTree> {
+    rest.insert(0, first);
+    rest
+}
+
+// Test Functions
+#[test]
+fn test_earley_ambiguous_left_recursive() {
+    let g = Grammar::parse("E -> E + E | a").unwrap();
+
+    let earley = Earley::new(&g, "a+a+a");
+    assert!(earley.accepted());
+    let trees: Vec<String> = earley
+        .parse_trees(10)
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+    assert_eq!(
+        trees,
+        vec![
+            "E(E(a) + E(E(a) + E(a)))".to_string(),
+            "E(E(E(a) + E(a)) + E(a))".to_string(),
+        ]
+    );
+    assert_eq!(earley.parse_trees(1).len(), 1);
+    assert!(!Earley::new(&g, "a+").accepted());
+
+    // ε-productions and nullable nonterminals
+    let g = Grammar::parse("S -> A S b | ε\nA -> a | ε").unwrap();
+    assert!(Earley::new(&g, "").accepted());
+    assert!(Earley::new(&g, "abb").accepted());
+    assert!(!Earley::new(&g, "ba").accepted());
+}
@@ -0,0 +1,432 @@
+//! Nondeterministic finite-state transducers
+//!
+//! A transducer file is a text file with one statement per line:
+//!
+//! ```text
+//! start 1
+//! accept 1 3
+//! 1 -> 2 a:xy
+//! 2 -> 3 ε:z
+//! ```
+//!
+//! Each arc reads one character or `ε` and writes a string, which may be
+//! `ε`. States are numbered from 1, `→` may be used for the arrow and `#`
+//! starts a comment.
+
+use crate::grammar::{Grammar, Production, Symbol};
+use crate::regular;
+use crate::NFA;
+use automata_draw::graphviz::Edges;
+use automata_draw::mermaid::StateDiagram;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+// *********************************************************************
+/// # A single arc `from -> to input:output`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arc {
+    /// State number (1 relative) the arc leaves
+    pub from: usize,
+
+    /// State number (1 relative) the arc enters
+    pub to: usize,
+
+    /// Character read, none for an ε-arc
+    pub input: Option<char>,
+
+    /// String written, empty for ε
+    pub output: String,
+}
+
+// *********************************************************************
+/// # Finite State Transducer Structure
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct FST {
+    /// Number of states
+    pub states: usize,
+
+    /// State number (1 relative) for the start state
+    pub start: usize,
+
+    /// Set of accept states (1 relative)
+    pub accept: Vec<usize>,
+
+    /// Arcs in the order they were defined
+    pub arcs: Vec<Arc>,
+}
+
+// *********************************************************************
+/// # Which side of the arcs a projection keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The characters read
+    Input,
+
+    /// The strings written
+    Output,
+}
+
+// *********************************************************************
+/// Implement the methods of the FST structure
+impl FST {
+    /// Create and return a transducer on the heap
+    ///
+    /// Load the transducer file specified, exiting with an error message
+    /// when it can not be parsed.
+    pub fn new_from_file(filename: &str) -> Box<FST> {
+        let text = std::fs::read_to_string(filename).expect("Unable to open input");
+
+        match FST::parse(&text) {
+            Ok(fst) => Box::new(fst),
+            Err(e) => {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Parse the text of a transducer file
+    pub fn parse(text: &str) -> Result<FST, String> {
+        let mut start = None;
+        let mut accept = Vec::new();
+        let mut arcs = Vec::new();
+
+        for (lnum, line) in text.lines().enumerate() {
+            // strip comments and skip blank lines
+            let line = match line.find('#') {
+                Some(i) => &line[..i],
+                None => line,
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let state = |word: &str| match word.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("Invalid state({}) in line {}", word, lnum + 1)),
+            };
+
+            match words.as_slice() {
+                [] => (),
+                ["start", n] => start = Some(state(n)?),
+                ["accept", states @ ..] => {
+                    for n in states {
+                        accept.push(state(n)?);
+                    }
+                }
+                [from, "->", to, label] | [from, "→", to, label] => {
+                    let mut chars = label.chars();
+                    let input = match chars.next() {
+                        Some('ε') => None,
+                        c => c,
+                    };
+                    if chars.next() != Some(':') {
+                        return Err(format!(
+                            "Label {} in line {} should be input:output",
+                            label,
+                            lnum + 1
+                        ));
+                    }
+                    let output: String = chars.collect();
+                    arcs.push(Arc {
+                        from: state(from)?,
+                        to: state(to)?,
+                        input,
+                        output: if output == "ε" {
+                            String::new()
+                        } else {
+                            output
+                        },
+                    });
+                }
+                _ => return Err(format!("Can not read line {}", lnum + 1)),
+            }
+        }
+
+        let start = start.ok_or_else(|| "Missing start state".to_string())?;
+        let states = arcs
+            .iter()
+            .flat_map(|a| vec![a.from, a.to])
+            .chain(accept.iter().copied())
+            .fold(start, usize::max);
+        Ok(FST {
+            states,
+            start,
+            accept,
+            arcs,
+        })
+    }
+
+    /// Characters read by the arcs, in order of first appearance
+    pub fn alphabet(&self) -> Vec<char> {
+        let mut alphabet = Vec::new();
+        for c in self.arcs.iter().filter_map(|a| a.input) {
+            if !alphabet.contains(&c) {
+                alphabet.push(c);
+            }
+        }
+        alphabet
+    }
+
+    /// Every string the transducer writes for `input`, sorted
+    ///
+    /// Paths that go around a cycle of ε-arcs keep writing output, so the
+    /// outputs of a path are only followed while they are no longer than
+    /// those of a path that never visits a state twice at one position.
+    pub fn apply(&self, input: &str) -> Vec<String> {
+        let chars: Vec<char> = input.chars().collect();
+        let longest = self.arcs.iter().map(|a| a.output.len()).max().unwrap_or(0);
+        let bound = (chars.len() + 1) * self.states * longest;
+
+        // (state, characters read, output so far)
+        let start = (self.start, 0, String::new());
+        let mut seen: HashSet<(usize, usize, String)> = HashSet::new();
+        seen.insert(start.clone());
+        let mut queue = VecDeque::from(vec![start]);
+        let mut outputs = Vec::new();
+
+        while let Some((state, pos, output)) = queue.pop_front() {
+            if pos == chars.len() && self.accept.contains(&state) && !outputs.contains(&output) {
+                outputs.push(output.clone());
+            }
+            for arc in self.arcs.iter().filter(|a| a.from == state) {
+                let next_pos = match arc.input {
+                    None => pos,
+                    Some(c) if chars.get(pos) == Some(&c) => pos + 1,
+                    Some(_) => continue,
+                };
+                let next = (arc.to, next_pos, format!("{}{}", output, arc.output));
+                if next.2.len() <= bound && seen.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        outputs.sort();
+        outputs
+    }
+
+    /// Equivalent transducer writing at most one character per arc
+    ///
+    /// An arc writing more is split into a chain through new states, with
+    /// the later characters written by ε-arcs.
+    fn split_outputs(&self) -> FST {
+        let mut fst = FST {
+            arcs: Vec::new(),
+            ..self.clone()
+        };
+        for arc in &self.arcs {
+            let chars: Vec<char> = arc.output.chars().collect();
+            if chars.len() <= 1 {
+                fst.arcs.push(arc.clone());
+                continue;
+            }
+            let mut from = arc.from;
+            for (i, c) in chars.iter().enumerate() {
+                let to = if i + 1 == chars.len() {
+                    arc.to
+                } else {
+                    fst.states += 1;
+                    fst.states
+                };
+                fst.arcs.push(Arc {
+                    from,
+                    to,
+                    input: if i == 0 { arc.input } else { None },
+                    output: c.to_string(),
+                });
+                from = to;
+            }
+        }
+        fst
+    }
+
+    /// Transducer writing what `second` writes for what this one writes
+    ///
+    /// Its states are the pairs of states of the two transducers reachable
+    /// from the pair of start states. A pair moves when the first writes
+    /// nothing, when the second reads nothing, or when the first writes
+    /// the character the second reads.
+    pub fn compose(&self, second: &FST) -> FST {
+        let first = self.split_outputs();
+        let mut pairs = vec![(first.start, second.start)];
+        let mut arcs = Vec::new();
+        let mut n = 0;
+        while n < pairs.len() {
+            let (p, q) = pairs[n];
+            let mut moves: Vec<((usize, usize), Option<char>, String)> = Vec::new();
+            for a in first.arcs.iter().filter(|a| a.from == p) {
+                match a.output.chars().next() {
+                    None => moves.push(((a.to, q), a.input, String::new())),
+                    Some(c) => {
+                        for b in second.arcs.iter().filter(|b| b.from == q) {
+                            if b.input == Some(c) {
+                                moves.push(((a.to, b.to), a.input, b.output.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+            for b in second
+                .arcs
+                .iter()
+                .filter(|b| b.from == q && b.input.is_none())
+            {
+                moves.push(((p, b.to), None, b.output.clone()));
+            }
+
+            for (pair, input, output) in moves {
+                let to = match pairs.iter().position(|x| *x == pair) {
+                    Some(i) => i + 1,
+                    None => {
+                        pairs.push(pair);
+                        pairs.len()
+                    }
+                };
+                let arc = Arc {
+                    from: n + 1,
+                    to,
+                    input,
+                    output,
+                };
+                if !arcs.contains(&arc) {
+                    arcs.push(arc);
+                }
+            }
+            n += 1;
+        }
+
+        FST {
+            states: pairs.len(),
+            start: 1,
+            accept: (1..=pairs.len())
+                .filter(|i| {
+                    let (p, q) = pairs[i - 1];
+                    first.accept.contains(&p) && second.accept.contains(&q)
+                })
+                .collect(),
+            arcs,
+        }
+    }
+
+    /// NFA for the strings read or written along accepting paths
+    ///
+    /// The arcs become the productions of a right-linear grammar, state
+    /// `qn` becoming `Qn`, which is then turned into an NFA.
+    pub fn projection(&self, side: Side) -> NFA {
+        let name = |state: usize| format!("Q{}", state);
+        let mut productions: Vec<Production> = Vec::new();
+        for arc in &self.arcs {
+            let mut body: Vec<Symbol> = match side {
+                Side::Input => arc.input.iter().map(|c| Symbol::Terminal(*c)).collect(),
+                Side::Output => arc.output.chars().map(Symbol::Terminal).collect(),
+            };
+            body.push(Symbol::Nonterminal(name(arc.to)));
+            crate::grammar::push_unique(
+                &mut productions,
+                Production {
+                    head: name(arc.from),
+                    body,
+                },
+            );
+        }
+        for state in &self.accept {
+            crate::grammar::push_unique(
+                &mut productions,
+                Production {
+                    head: name(*state),
+                    body: vec![],
+                },
+            );
+        }
+
+        let grammar = Grammar {
+            start: name(self.start),
+            productions,
+        };
+        regular::grammar_to_nfa(&grammar)
+            .expect("A transducer gives a right-linear grammar")
+            .0
+    }
+
+    /// Write the graph to stdout, arcs labelled like `a:xy`
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        for state in &self.accept {
+            println!("\tnode [shape = doublecircle]; q{};", state);
+        }
+        println!("\tnode [shape=circle];");
+        println!("\tstart -> q{}", self.start);
+        self.edges().write();
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new(&format!("q{}", self.start));
+        diagram.accept = self.accept.iter().map(|q| format!("q{}", q)).collect();
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every arc
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for arc in &self.arcs {
+            edges.add(
+                &format!("q{}", arc.from),
+                &format!("q{}", arc.to),
+                &arc.to_string(),
+            );
+        }
+        edges
+    }
+}
+
+impl fmt::Display for Arc {
+    /// The label of the arc, like `a:xy` or `ε:ε`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let input = self.input.map_or("ε".to_string(), |c| c.to_string());
+        let output = if self.output.is_empty() {
+            "ε"
+        } else {
+            &self.output
+        };
+        write!(f, "{}:{}", input, output)
+    }
+}
+
+// Test Functions
+#[test]
+fn test_fst_apply_compose_project() {
+    // lowercase A and B and drop hyphens
+    let lower =
+        FST::parse("start 1\naccept 1\n1 -> 1 a:a\n1 -> 1 A:a\n1 -> 1 b:b\n1 -> 1 B:b\n1 -> 1 -:ε")
+            .unwrap();
+    assert_eq!(lower.apply("aB-b"), vec!["abb"]);
+    assert_eq!(lower.apply("c"), Vec::<String>::new());
+
+    // each b may be doubled, then an x is added at the end
+    let double =
+        FST::parse("start 1\naccept 2\n1 -> 1 a:a\n1 -> 1 b:b\n1 → 1 b:bb\n1 -> 2 ε:x  # end")
+            .unwrap();
+    assert_eq!(double.apply("ab"), vec!["abbx", "abx"]);
+
+    let both = lower.compose(&double);
+    assert_eq!(both.apply("aB-b"), vec!["abbbbx", "abbbx", "abbx"]);
+    assert_eq!(both.apply("-"), vec!["x"]);
+
+    // an ε-cycle writing output only follows the shortest trips round it
+    let cycle = FST::parse("start 1\naccept 1\n1 -> 1 ε:y\n1 -> 1 a:a").unwrap();
+    assert_eq!(cycle.apply(""), vec!["", "y"]);
+
+    let inputs = both.projection(Side::Input);
+    let outputs = both.projection(Side::Output);
+    for (s, accept) in [("", true), ("AB-ab", true), ("c", false)] {
+        assert_eq!(inputs.accepts(s), accept, "{}", s);
+    }
+    for (s, accept) in [("x", true), ("abbbx", true), ("ab", false), ("xa", false)] {
+        assert_eq!(outputs.accepts(s), accept, "{}", s);
+    }
+
+    assert!(FST::parse("accept 1\n1 -> 1 a:b").is_err());
+    assert!(FST::parse("start 1\n1 -> 1 ab").is_err());
+}
@@ -0,0 +1,773 @@
+//! Context free grammars
+//!
+//! A grammar file is a text file with one rule per line:
+//!
+//! ```text
+//! S -> a S b | ε
+//! ```
+//!
+//! Uppercase letters (optionally followed by digits, `_` or `'`) and names
+//! in angle brackets such as `<expr>` are nonterminals, every other
+//! character is a terminal. `ε` or an empty alternative is the empty
+//! string, `→` may be used for the arrow and `#` starts a comment. The
+//! head of the first rule is the start symbol.
+
+use automata_draw::graphviz::escape;
+use std::collections::HashSet;
+use std::fmt;
+
+// *********************************************************************
+/// # Grammar symbol
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symbol {
+    /// A single character of the input alphabet
+    Terminal(char),
+
+    /// A variable of the grammar
+    Nonterminal(String),
+}
+
+// *********************************************************************
+/// # Definition of a single production `head -> body`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Production {
+    /// Nonterminal on the left side
+    pub head: String,
+
+    /// Symbols on the right side, empty for an ε-production
+    pub body: Vec<Symbol>,
+}
+
+// *********************************************************************
+/// # Context Free Grammar Structure
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    /// Name of the start symbol
+    pub start: String,
+
+    /// All productions in the order they were defined
+    pub productions: Vec<Production>,
+}
+
+// *********************************************************************
+/// # Parse tree produced by the grammar parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTree {
+    /// Nonterminal name, terminal character or `ε`
+    pub label: String,
+
+    /// Subtrees from left to right, empty for a leaf
+    pub children: Vec<ParseTree>,
+}
+
+// *********************************************************************
+/// # One step of the simplification pipeline
+#[derive(Debug)]
+pub struct SimplificationStep {
+    /// What the step does
+    pub name: &'static str,
+
+    /// What the step found, such as the symbols it removed
+    pub note: String,
+
+    /// The grammar after the step
+    pub grammar: Grammar,
+}
+
+// *********************************************************************
+/// Implement the methods of the Symbol enum
+impl Symbol {
+    /// Is this a nonterminal named `name`
+    pub fn is_nonterminal(&self, name: &str) -> bool {
+        matches!(self, Symbol::Nonterminal(n) if n == name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Symbol::Terminal(c) => write!(f, "{}", c),
+            Symbol::Nonterminal(name) => write!(f, "{}", nonterminal_name(name)),
+        }
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Production structure
+impl Production {
+    /// Is this a unit production `A -> B`
+    pub fn is_unit(&self) -> bool {
+        matches!(self.body.as_slice(), [Symbol::Nonterminal(_)])
+    }
+}
+
+impl fmt::Display for Production {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {}",
+            nonterminal_name(&self.head),
+            body_string(&self.body)
+        )
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the Grammar structure
+impl Grammar {
+    /// Create and return a grammar on the heap
+    ///
+    /// Load the grammar file specified, exiting with an error message
+    /// when it can not be parsed.
+    pub fn new_from_file(filename: &str) -> Box<Grammar> {
+        let text = std::fs::read_to_string(filename).expect("Unable to open input");
+
+        match Grammar::parse(&text) {
+            Ok(grammar) => Box::new(grammar),
+            Err(e) => {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Parse the text of a grammar file
+    pub fn parse(text: &str) -> Result<Grammar, String> {
+        let mut productions: Vec<Production> = Vec::new();
+
+        for (lnum, line) in text.lines().enumerate() {
+            // strip comments and skip blank lines
+            let line = match line.find('#') {
+                Some(i) => &line[..i],
+                None => line,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (head, body) =
+                split_arrow(line).ok_or_else(|| format!("Missing '->' in line {}", lnum + 1))?;
+
+            let head = match tokenize(head)
+                .map_err(|e| format!("{} in line {}", e, lnum + 1))?
+                .as_slice()
+            {
+                [Symbol::Nonterminal(name)] => name.clone(),
+                _ => {
+                    return Err(format!(
+                        "Left side of line {} must be a single nonterminal",
+                        lnum + 1
+                    ))
+                }
+            };
+
+            for alternative in body.split('|') {
+                let body =
+                    tokenize(alternative).map_err(|e| format!("{} in line {}", e, lnum + 1))?;
+                push_unique(
+                    &mut productions,
+                    Production {
+                        head: head.clone(),
+                        body,
+                    },
+                );
+            }
+        }
+
+        if productions.is_empty() {
+            return Err("Grammar has no productions".to_string());
+        }
+
+        Ok(Grammar {
+            start: productions[0].head.clone(),
+            productions,
+        })
+    }
+
+    /// Nonterminals in order of first appearance, start symbol first
+    pub fn nonterminals(&self) -> Vec<String> {
+        let mut nonterminals = vec![self.start.clone()];
+        for p in &self.productions {
+            if !nonterminals.contains(&p.head) {
+                nonterminals.push(p.head.clone());
+            }
+            for symbol in &p.body {
+                if let Symbol::Nonterminal(name) = symbol {
+                    if !nonterminals.contains(name) {
+                        nonterminals.push(name.clone());
+                    }
+                }
+            }
+        }
+        nonterminals
+    }
+
+    /// Terminals in order of first appearance
+    pub fn terminals(&self) -> Vec<char> {
+        let mut terminals = Vec::new();
+        for p in &self.productions {
+            for symbol in &p.body {
+                if let Symbol::Terminal(c) = symbol {
+                    if !terminals.contains(c) {
+                        terminals.push(*c);
+                    }
+                }
+            }
+        }
+        terminals
+    }
+
+    /// Productions whose left side is `head`
+    pub fn productions_of<'a>(&'a self, head: &'a str) -> impl Iterator<Item = &'a Production> {
+        self.productions.iter().filter(move |p| p.head == head)
+    }
+
+    /// Return an unused nonterminal name built from `base`
+    pub fn fresh_nonterminal(&self, base: char) -> String {
+        fresh_name(&mut self.nonterminals(), base)
+    }
+
+    /// Does the start symbol appear on the right side of any production
+    pub fn start_on_right(&self) -> bool {
+        self.productions
+            .iter()
+            .any(|p| p.body.iter().any(|s| s.is_nonterminal(&self.start)))
+    }
+
+    /// Nonterminals that can derive the empty string
+    pub fn nullable(&self) -> HashSet<String> {
+        self.closure(|nullable, symbol| match symbol {
+            Symbol::Terminal(_) => false,
+            Symbol::Nonterminal(name) => nullable.contains(name),
+        })
+    }
+
+    /// Nonterminals that can derive some string of terminals
+    pub fn generating(&self) -> HashSet<String> {
+        self.closure(|generating, symbol| match symbol {
+            Symbol::Terminal(_) => true,
+            Symbol::Nonterminal(name) => generating.contains(name),
+        })
+    }
+
+    /// Nonterminals that appear in some sentential form derived from the
+    /// start symbol
+    pub fn reachable(&self) -> HashSet<String> {
+        let mut reachable = HashSet::new();
+        let mut pending = vec![self.start.clone()];
+        reachable.insert(self.start.clone());
+
+        while let Some(head) = pending.pop() {
+            for p in self.productions_of(&head) {
+                for symbol in &p.body {
+                    if let Symbol::Nonterminal(name) = symbol {
+                        if reachable.insert(name.clone()) {
+                            pending.push(name.clone());
+                        }
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Fixed point over the productions: a head joins the set once every
+    /// symbol of one of its bodies satisfies `member`
+    fn closure(&self, member: impl Fn(&HashSet<String>, &Symbol) -> bool) -> HashSet<String> {
+        let mut set = HashSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &self.productions {
+                if !set.contains(&p.head) && p.body.iter().all(|s| member(&set, s)) {
+                    set.insert(p.head.clone());
+                    changed = true;
+                }
+            }
+        }
+        set
+    }
+
+    /// Copy of the grammar keeping only the productions accepted by `keep`
+    fn filter(&self, keep: impl Fn(&Production) -> bool) -> Grammar {
+        Grammar {
+            start: self.start.clone(),
+            productions: self
+                .productions
+                .iter()
+                .filter(|p| keep(p))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Remove every production that mentions a non-generating symbol
+    pub fn remove_non_generating(&self) -> Grammar {
+        let generating = self.generating();
+        self.filter(|p| {
+            generating.contains(&p.head)
+                && p.body.iter().all(|s| match s {
+                    Symbol::Terminal(_) => true,
+                    Symbol::Nonterminal(name) => generating.contains(name),
+                })
+        })
+    }
+
+    /// Remove every production of a symbol unreachable from the start
+    pub fn remove_unreachable(&self) -> Grammar {
+        let reachable = self.reachable();
+        self.filter(|p| reachable.contains(&p.head))
+    }
+
+    /// Remove non-generating and then unreachable symbols
+    pub fn remove_useless(&self) -> Grammar {
+        self.remove_non_generating().remove_unreachable()
+    }
+
+    /// Remove ε-productions
+    ///
+    /// Every production is replaced by all the ways of leaving out nullable
+    /// symbols from its body. When the language contains ε only the start
+    /// symbol keeps an ε-production, with a new start symbol introduced if
+    /// the old one appears on a right side.
+    pub fn remove_epsilon(&self) -> Grammar {
+        let nullable = self.nullable();
+        let mut result = Grammar {
+            start: self.start.clone(),
+            productions: Vec::new(),
+        };
+
+        for p in &self.productions {
+            for body in drop_nullable(&p.body, &nullable) {
+                if !body.is_empty() {
+                    push_unique(
+                        &mut result.productions,
+                        Production {
+                            head: p.head.clone(),
+                            body,
+                        },
+                    );
+                }
+            }
+        }
+
+        if nullable.contains(&self.start) {
+            if result.start_on_right() {
+                let new_start = result.fresh_nonterminal(start_base(&self.start));
+                result.productions.insert(
+                    0,
+                    Production {
+                        head: new_start.clone(),
+                        body: vec![Symbol::Nonterminal(self.start.clone())],
+                    },
+                );
+                result.start = new_start;
+            }
+            result.productions.push(Production {
+                head: result.start.clone(),
+                body: vec![],
+            });
+        }
+
+        result
+    }
+
+    /// Remove unit productions `A -> B`
+    ///
+    /// For every pair `A =>* B` using unit productions only, `A` receives
+    /// copies of the non-unit productions of `B`.
+    pub fn remove_unit(&self) -> Grammar {
+        let mut result = Grammar {
+            start: self.start.clone(),
+            productions: Vec::new(),
+        };
+
+        for head in self.nonterminals() {
+            // all nonterminals reachable from head by unit productions
+            let mut unit_pairs = vec![head.clone()];
+            let mut i = 0;
+            while i < unit_pairs.len() {
+                let current = unit_pairs[i].clone();
+                for p in self.productions_of(&current) {
+                    if let [Symbol::Nonterminal(name)] = p.body.as_slice() {
+                        if !unit_pairs.contains(name) {
+                            unit_pairs.push(name.clone());
+                        }
+                    }
+                }
+                i += 1;
+            }
+
+            for name in &unit_pairs {
+                for p in self.productions_of(name).filter(|p| !p.is_unit()) {
+                    push_unique(
+                        &mut result.productions,
+                        Production {
+                            head: head.clone(),
+                            body: p.body.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Run the simplification steps in order, keeping the grammar after
+    /// each one
+    ///
+    /// Removing ε and unit productions can leave symbols useless again, so
+    /// a last step removes those when there are any.
+    pub fn simplification_steps(&self) -> Vec<SimplificationStep> {
+        let mut steps = Vec::new();
+
+        let generating = self.generating();
+        let grammar = self.remove_non_generating();
+        steps.push(SimplificationStep {
+            name: "Remove non-generating symbols",
+            note: removed_note(self, &grammar, |n| !generating.contains(n)),
+            grammar,
+        });
+
+        let before = &steps[0].grammar;
+        let reachable = before.reachable();
+        let grammar = before.remove_unreachable();
+        steps.push(SimplificationStep {
+            name: "Remove unreachable symbols",
+            note: removed_note(before, &grammar, |n| !reachable.contains(n)),
+            grammar,
+        });
+
+        let before = &steps[1].grammar;
+        let nullable = before.nullable();
+        let mut names: Vec<String> = before
+            .nonterminals()
+            .into_iter()
+            .filter(|n| nullable.contains(n))
+            .map(|n| nonterminal_name(&n))
+            .collect();
+        let grammar = before.remove_epsilon();
+        let note = if grammar.start != before.start {
+            format!(
+                "Nullable: {{{}}}, new start symbol {} keeps ε",
+                names.join(", "),
+                nonterminal_name(&grammar.start)
+            )
+        } else {
+            format!("Nullable: {{{}}}", names.join(", "))
+        };
+        steps.push(SimplificationStep {
+            name: "Remove ε-productions",
+            note,
+            grammar,
+        });
+
+        let before = &steps[2].grammar;
+        names = before
+            .productions
+            .iter()
+            .filter(|p| p.is_unit())
+            .map(|p| p.to_string())
+            .collect();
+        let grammar = before.remove_unit();
+        steps.push(SimplificationStep {
+            name: "Remove unit productions",
+            note: format!("Removed: {{{}}}", names.join(", ")),
+            grammar,
+        });
+
+        let before = &steps[3].grammar;
+        let grammar = before.remove_useless();
+        if grammar.productions.len() != before.productions.len() {
+            let kept = grammar.nonterminals();
+            steps.push(SimplificationStep {
+                name: "Remove symbols left useless",
+                note: removed_note(before, &grammar, |n| !kept.contains(&n.to_string())),
+                grammar,
+            });
+        }
+
+        steps
+    }
+
+    /// The grammar after every simplification step
+    pub fn simplify(&self) -> Grammar {
+        self.simplification_steps().pop().unwrap().grammar
+    }
+}
+
+impl fmt::Display for Grammar {
+    /// One line per nonterminal with the alternatives separated by `|`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.productions.is_empty() {
+            return writeln!(f, "{} has no productions", nonterminal_name(&self.start));
+        }
+        for head in self.nonterminals() {
+            let bodies: Vec<String> = self
+                .productions_of(&head)
+                .map(|p| body_string(&p.body))
+                .collect();
+            if !bodies.is_empty() {
+                writeln!(f, "{} -> {}", nonterminal_name(&head), bodies.join(" | "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the ParseTree structure
+impl ParseTree {
+    /// Create a tree with no children
+    pub fn leaf(label: &str) -> ParseTree {
+        ParseTree {
+            label: label.to_string(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Write the tree to stdout
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\tordering=out;");
+        println!("\tnode [shape=circle];");
+        for line in self.graphviz_lines("n") {
+            println!("\t{}", line);
+        }
+        println!("}}");
+    }
+
+    /// Node and edge statements for the tree, node names start with `prefix`
+    pub fn graphviz_lines(&self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut count = 0;
+        self.add_graphviz_lines(prefix, &mut count, &mut lines);
+        lines
+    }
+
+    /// Append the statements for this subtree, returning its node name
+    fn add_graphviz_lines(
+        &self,
+        prefix: &str,
+        count: &mut usize,
+        lines: &mut Vec<String>,
+    ) -> String {
+        let name = format!("{}{}", prefix, count);
+        *count += 1;
+
+        let shape = if self.children.is_empty() {
+            "plaintext"
+        } else {
+            "circle"
+        };
+        lines.push(format!(
+            "{} [label=\"{}\", shape={}];",
+            name,
+            escape(&self.label),
+            shape
+        ));
+        for child in &self.children {
+            let child_name = child.add_graphviz_lines(prefix, count, lines);
+            lines.push(format!("{} -> {};", name, child_name));
+        }
+        name
+    }
+}
+
+impl fmt::Display for ParseTree {
+    /// Bracketed form such as `S(a S(ε) b)`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label)?;
+        if !self.children.is_empty() {
+            let children: Vec<String> = self.children.iter().map(|c| c.to_string()).collect();
+            write!(f, "({})", children.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// *********************************************************************
+/// Printable form of a nonterminal, using angle brackets unless the name
+/// reads back as a single nonterminal
+pub fn nonterminal_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let simple = matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_digit() || c == '_' || c == '\'');
+    if simple {
+        name.to_string()
+    } else {
+        format!("<{}>", name)
+    }
+}
+
+// *********************************************************************
+/// Printable form of a production body, `ε` when it is empty
+pub fn body_string(body: &[Symbol]) -> String {
+    if body.is_empty() {
+        "ε".to_string()
+    } else {
+        body.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+// *********************************************************************
+/// Add a production unless it is already present
+pub fn push_unique(productions: &mut Vec<Production>, production: Production) {
+    if !productions.contains(&production) {
+        productions.push(production);
+    }
+}
+
+// *********************************************************************
+/// Return the first name `base0`, `base1`, ... not in `used` and add it
+pub fn fresh_name(used: &mut Vec<String>, base: char) -> String {
+    let name = (0..)
+        .map(|i| format!("{}{}", base, i))
+        .find(|name| !used.contains(name))
+        .unwrap();
+    used.push(name.clone());
+    name
+}
+
+// *********************************************************************
+/// Describe the nonterminals of `before` matching `removed` and how many
+/// productions went with them
+fn removed_note(before: &Grammar, after: &Grammar, removed: impl Fn(&str) -> bool) -> String {
+    let names: Vec<String> = before
+        .nonterminals()
+        .iter()
+        .filter(|n| removed(n))
+        .map(|n| nonterminal_name(n))
+        .collect();
+    format!(
+        "Removed: {{{}}}, {} production(s) dropped",
+        names.join(", "),
+        before.productions.len() - after.productions.len()
+    )
+}
+
+// *********************************************************************
+/// Base letter used for a new start symbol
+fn start_base(start: &str) -> char {
+    match start.chars().next() {
+        Some(c) if c.is_ascii_uppercase() => c,
+        _ => 'S',
+    }
+}
+
+// *********************************************************************
+/// Split a rule at `->` or `→`
+fn split_arrow(line: &str) -> Option<(&str, &str)> {
+    if let Some(i) = line.find("->") {
+        Some((&line[..i], &line[i + 2..]))
+    } else {
+        line.find('→')
+            .map(|i| (&line[..i], &line[i + '→'.len_utf8()..]))
+    }
+}
+
+// *********************************************************************
+/// Split one alternative into symbols
+fn tokenize(text: &str) -> Result<Vec<Symbol>, String> {
+    let mut symbols = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() || c == 'ε' {
+            continue;
+        }
+
+        if c == '<' {
+            // <name> nonterminal
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('>') => break,
+                    Some(ch) => name.push(ch),
+                    None => return Err("Unterminated '<'".to_string()),
+                }
+            }
+            if name.is_empty() {
+                return Err("Empty nonterminal name '<>'".to_string());
+            }
+            symbols.push(Symbol::Nonterminal(name));
+        } else if c.is_ascii_uppercase() {
+            // uppercase letter with optional digits, '_' or '
+            let mut name = c.to_string();
+            while let Some(&next) = chars.peek() {
+                if next.is_ascii_digit() || next == '_' || next == '\'' {
+                    name.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            symbols.push(Symbol::Nonterminal(name));
+        } else {
+            symbols.push(Symbol::Terminal(c));
+        }
+    }
+
+    Ok(symbols)
+}
+
+// *********************************************************************
+/// Every body obtained by leaving out any subset of the nullable symbols
+fn drop_nullable(body: &[Symbol], nullable: &HashSet<String>) -> Vec<Vec<Symbol>> {
+    let mut bodies: Vec<Vec<Symbol>> = vec![vec![]];
+    for symbol in body {
+        let optional = matches!(symbol, Symbol::Nonterminal(name) if nullable.contains(name));
+        let mut next = Vec::new();
+        for partial in bodies {
+            let mut with = partial.clone();
+            with.push(symbol.clone());
+            next.push(with);
+            if optional {
+                next.push(partial);
+            }
+        }
+        bodies = next;
+    }
+    bodies
+}
+
+// Test Functions
+#[test]
+fn test_parse_and_remove_epsilon() {
+    let g = Grammar::parse("S -> a S b | ε\n").unwrap();
+    assert_eq!(g.start, "S");
+    assert_eq!(g.productions.len(), 2);
+    assert!(g.nullable().contains("S"));
+
+    // S appears on a right side so a new start symbol keeps ε
+    let g = g.remove_epsilon();
+    assert_eq!(g.start, "S0");
+    assert_eq!(g.to_string(), "S0 -> S | ε\nS -> a S b | a b\n");
+}
+
+#[test]
+fn test_simplification_steps() {
+    let g = Grammar::parse("S -> A | a B | C\nA -> a A | ε\nB -> B b\nC -> c\nD -> d").unwrap();
+    let steps = g.simplification_steps();
+    let names: Vec<&str> = steps.iter().map(|s| s.name).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Remove non-generating symbols",
+            "Remove unreachable symbols",
+            "Remove ε-productions",
+            "Remove unit productions",
+            "Remove symbols left useless",
+        ]
+    );
+    assert_eq!(steps[0].note, "Removed: {B}, 2 production(s) dropped");
+    assert_eq!(steps[1].note, "Removed: {D}, 1 production(s) dropped");
+    assert_eq!(
+        g.simplify().to_string(),
+        "S -> ε | a A | a | c\nA -> a A | a\n"
+    );
+}
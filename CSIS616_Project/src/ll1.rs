@@ -0,0 +1,407 @@
+//! LL(1) analysis and predictive parsing
+//!
+//! `$` marks the end of the input in FOLLOW sets and in the parse table,
+//! so it should not be used as a terminal of the grammar.
+
+use crate::grammar::{body_string, nonterminal_name, Grammar, Production, Symbol};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// End of input marker
+pub const END: char = '$';
+
+// *********************************************************************
+/// # Two productions competing for one LL(1) table cell
+#[derive(Debug)]
+pub struct Conflict {
+    /// Nonterminal of the table row
+    pub nonterminal: String,
+
+    /// Lookahead terminal of the table column
+    pub lookahead: char,
+
+    /// Production already in the cell
+    pub first: Production,
+
+    /// Production that also wants the cell
+    pub second: Production,
+}
+
+// *********************************************************************
+/// # One step of a predictive parse
+#[derive(Debug)]
+pub struct Step {
+    /// Stack contents, top first
+    pub stack: String,
+
+    /// Input not yet matched, followed by `$`
+    pub input: String,
+
+    /// What the parser did
+    pub action: String,
+}
+
+// *********************************************************************
+/// # LL(1) analysis of a grammar
+#[derive(Debug)]
+pub struct LL1 {
+    /// The grammar analysed
+    grammar: Grammar,
+
+    /// Nonterminals deriving ε
+    nullable: HashSet<String>,
+
+    /// FIRST set of every nonterminal
+    first: HashMap<String, BTreeSet<char>>,
+
+    /// FOLLOW set of every nonterminal
+    follow: HashMap<String, BTreeSet<char>>,
+
+    /// Production indices for each (nonterminal, lookahead) cell
+    table: BTreeMap<(String, char), Vec<usize>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the LL1 structure
+impl LL1 {
+    /// Compute nullable, FIRST and FOLLOW sets and the parse table
+    pub fn new(grammar: &Grammar) -> LL1 {
+        let nonterminals = grammar.nonterminals();
+        let mut ll1 = LL1 {
+            grammar: grammar.clone(),
+            nullable: grammar.nullable(),
+            first: nonterminals
+                .iter()
+                .map(|n| (n.clone(), BTreeSet::new()))
+                .collect(),
+            follow: nonterminals
+                .iter()
+                .map(|n| (n.clone(), BTreeSet::new()))
+                .collect(),
+            table: BTreeMap::new(),
+        };
+
+        // FIRST: fixed point over the productions
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &grammar.productions {
+                let (first, _) = ll1.first_of(&p.body);
+                let set = ll1.first.get_mut(&p.head).unwrap();
+                let before = set.len();
+                set.extend(first);
+                changed |= set.len() != before;
+            }
+        }
+
+        // FOLLOW: what can come after each nonterminal occurrence
+        ll1.follow.get_mut(&grammar.start).unwrap().insert(END);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &grammar.productions {
+                for (i, symbol) in p.body.iter().enumerate() {
+                    if let Symbol::Nonterminal(name) = symbol {
+                        let (mut follow, nullable) = ll1.first_of(&p.body[i + 1..]);
+                        if nullable {
+                            follow.extend(ll1.follow[&p.head].iter().cloned());
+                        }
+                        let set = ll1.follow.get_mut(name).unwrap();
+                        let before = set.len();
+                        set.extend(follow);
+                        changed |= set.len() != before;
+                    }
+                }
+            }
+        }
+
+        // Table: A -> α goes under FIRST(α), and FOLLOW(A) when α is nullable
+        for (n, p) in grammar.productions.iter().enumerate() {
+            let (mut lookaheads, nullable) = ll1.first_of(&p.body);
+            if nullable {
+                lookaheads.extend(ll1.follow[&p.head].iter().cloned());
+            }
+            for c in lookaheads {
+                ll1.table.entry((p.head.clone(), c)).or_default().push(n);
+            }
+        }
+
+        ll1
+    }
+
+    /// FOLLOW set of a nonterminal
+    pub fn follow(&self, name: &str) -> &BTreeSet<char> {
+        &self.follow[name]
+    }
+
+    /// FIRST set of a sequence of symbols and whether it derives ε
+    pub fn first_of(&self, symbols: &[Symbol]) -> (BTreeSet<char>, bool) {
+        let mut first = BTreeSet::new();
+        for symbol in symbols {
+            match symbol {
+                Symbol::Terminal(c) => {
+                    first.insert(*c);
+                    return (first, false);
+                }
+                Symbol::Nonterminal(name) => {
+                    first.extend(self.first[name].iter().cloned());
+                    if !self.nullable.contains(name) {
+                        return (first, false);
+                    }
+                }
+            }
+        }
+        (first, true)
+    }
+
+    /// Print the nullable nonterminals and the FIRST and FOLLOW sets
+    pub fn print_sets(&self) {
+        let nonterminals = self.grammar.nonterminals();
+        let nullable: Vec<String> = nonterminals
+            .iter()
+            .filter(|n| self.nullable.contains(*n))
+            .map(|n| nonterminal_name(n))
+            .collect();
+        println!("Nullable: {{{}}}", nullable.join(", "));
+
+        println!("FIRST sets:");
+        for n in &nonterminals {
+            println!(
+                "\tFIRST({}) = {}",
+                nonterminal_name(n),
+                set_string(&self.first[n])
+            );
+        }
+        println!("FOLLOW sets:");
+        for n in &nonterminals {
+            println!(
+                "\tFOLLOW({}) = {}",
+                nonterminal_name(n),
+                set_string(&self.follow[n])
+            );
+        }
+    }
+
+    /// Print the parse table, one row per nonterminal
+    pub fn print_table(&self) {
+        let mut columns = self.grammar.terminals();
+        columns.push(END);
+
+        let rows: Vec<(String, Vec<String>)> = self
+            .grammar
+            .nonterminals()
+            .iter()
+            .map(|n| {
+                let cells = columns
+                    .iter()
+                    .map(|c| match self.table.get(&(n.clone(), *c)) {
+                        Some(entries) => entries
+                            .iter()
+                            .map(|i| body_string(&self.grammar.productions[*i].body))
+                            .collect::<Vec<String>>()
+                            .join(" / "),
+                        None => String::new(),
+                    })
+                    .collect();
+                (nonterminal_name(n), cells)
+            })
+            .collect();
+
+        let head_width = rows
+            .iter()
+            .map(|(n, _)| n.chars().count())
+            .max()
+            .unwrap_or(1);
+        let width = rows
+            .iter()
+            .flat_map(|(_, cells)| cells.iter().map(|c| c.chars().count()))
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        println!("LL(1) table:");
+        let header: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{:<w$}", c, w = width))
+            .collect();
+        println!(
+            "{:<hw$} | {}",
+            "",
+            header.join(" | ").trim_end(),
+            hw = head_width
+        );
+        for (name, cells) in rows {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|c| format!("{:<w$}", c, w = width))
+                .collect();
+            println!(
+                "{}",
+                format!("{:<hw$} | {}", name, cells.join(" | "), hw = head_width).trim_end()
+            );
+        }
+    }
+
+    /// Every pair of productions sharing a table cell
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for ((nonterminal, lookahead), entries) in &self.table {
+            for (i, first) in entries.iter().enumerate() {
+                for second in &entries[i + 1..] {
+                    conflicts.push(Conflict {
+                        nonterminal: nonterminal.clone(),
+                        lookahead: *lookahead,
+                        first: self.grammar.productions[*first].clone(),
+                        second: self.grammar.productions[*second].clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Run the table driven parser, returning acceptance and every step
+    pub fn parse(&self, input: &str) -> (bool, Vec<Step>) {
+        let input: Vec<char> = input.chars().collect();
+        let mut stack = vec![Symbol::Nonterminal(self.grammar.start.clone())];
+        let mut pos = 0;
+        let mut steps = Vec::new();
+
+        loop {
+            let lookahead = input.get(pos).copied().unwrap_or(END);
+            let mut step = Step {
+                stack: stack
+                    .iter()
+                    .rev()
+                    .map(|s| s.to_string())
+                    .chain(std::iter::once(END.to_string()))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                input: input[pos..].iter().chain(std::iter::once(&END)).collect(),
+                action: String::new(),
+            };
+
+            let accepted = match stack.pop() {
+                None => Some(lookahead == END),
+                Some(Symbol::Terminal(c)) => {
+                    if c == lookahead {
+                        step.action = format!("match {}", c);
+                        pos += 1;
+                        None
+                    } else {
+                        step.action = format!("error: expected {}", c);
+                        Some(false)
+                    }
+                }
+                Some(Symbol::Nonterminal(name)) => match self.table.get(&(name.clone(), lookahead))
+                {
+                    Some(entries) => {
+                        let p = &self.grammar.productions[entries[0]];
+                        step.action = p.to_string();
+                        stack.extend(p.body.iter().rev().cloned());
+                        None
+                    }
+                    None => {
+                        step.action = format!(
+                            "error: no rule for [{}, {}]",
+                            nonterminal_name(&name),
+                            lookahead
+                        );
+                        Some(false)
+                    }
+                },
+            };
+
+            if let Some(accepted) = accepted {
+                if step.action.is_empty() {
+                    step.action = if accepted {
+                        "accept"
+                    } else {
+                        "error: input left over"
+                    }
+                    .to_string();
+                }
+                steps.push(step);
+                return (accepted, steps);
+            }
+            steps.push(step);
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {}]: {}  vs  {}",
+            nonterminal_name(&self.nonterminal),
+            self.lookahead,
+            self.first,
+            self.second
+        )
+    }
+}
+
+// *********************************************************************
+/// Print the steps of a parse as aligned columns
+pub fn print_steps(steps: &[Step]) {
+    let stack_width = steps
+        .iter()
+        .map(|s| s.stack.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let input_width = steps
+        .iter()
+        .map(|s| s.input.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    println!("Parsing steps:");
+    println!(
+        "{:<sw$}  {:>iw$}  Action",
+        "Stack",
+        "Input",
+        sw = stack_width,
+        iw = input_width
+    );
+    for step in steps {
+        println!(
+            "{:<sw$}  {:>iw$}  {}",
+            step.stack,
+            step.input,
+            step.action,
+            sw = stack_width,
+            iw = input_width
+        );
+    }
+}
+
+// *********************************************************************
+/// Printable form of a set of terminals
+fn set_string(set: &BTreeSet<char>) -> String {
+    let items: Vec<String> = set.iter().map(|c| c.to_string()).collect();
+    format!("{{{}}}", items.join(", "))
+}
+
+// Test Functions
+#[test]
+fn test_ll1_sets_and_parse() {
+    let g = Grammar::parse("E -> T E'\nE' -> + T E' | ε\nT -> a | ( E )").unwrap();
+    let ll1 = LL1::new(&g);
+    assert!(ll1.conflicts().is_empty());
+    assert_eq!(ll1.first["E"], "(a".chars().collect());
+    assert_eq!(ll1.follow["T"], "$)+".chars().collect());
+
+    let (accepted, steps) = ll1.parse("a+(a)");
+    assert!(accepted);
+    assert_eq!(steps[0].action, "E -> T E'");
+    assert_eq!(steps.last().unwrap().action, "accept");
+    assert!(!ll1.parse("a+").0);
+
+    let g = Grammar::parse("S -> a | a b").unwrap();
+    let conflicts = LL1::new(&g).conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].to_string(), "[S, a]: S -> a  vs  S -> a b");
+}
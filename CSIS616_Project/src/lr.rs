@@ -0,0 +1,547 @@
+//! LR(0) item automaton with SLR(1) and LALR(1) parse tables
+//!
+//! The grammar is augmented with a new start production `S' -> S`, which
+//! is always production 0.
+
+use crate::grammar::{nonterminal_name, Grammar, Production, Symbol};
+use crate::ll1::{Step, END, LL1};
+use automata_draw::graphviz::{escape, Edges};
+use automata_draw::mermaid::StateDiagram;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// LR(0) item: production index and dot position
+type Item = (usize, usize);
+
+// *********************************************************************
+/// # Entry of the ACTION table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Push the lookahead and go to the state
+    Shift(usize),
+
+    /// Reduce by the production with this index
+    Reduce(usize),
+
+    /// The input has been parsed
+    Accept,
+}
+
+// *********************************************************************
+/// # Canonical collection of LR(0) item sets
+#[derive(Debug)]
+pub struct LR0 {
+    /// The augmented grammar
+    grammar: Grammar,
+
+    /// Closed item set of each state, state 0 is the start state
+    states: Vec<Vec<Item>>,
+
+    /// Transitions between states on grammar symbols
+    goto: BTreeMap<(usize, Symbol), usize>,
+}
+
+// *********************************************************************
+/// # ACTION and GOTO tables built from the LR(0) automaton
+#[derive(Debug)]
+pub struct LrTable {
+    /// `SLR(1)` or `LALR(1)`
+    pub kind: &'static str,
+
+    /// Actions for each (state, lookahead), more than one is a conflict
+    actions: BTreeMap<(usize, char), Vec<Action>>,
+
+    /// State to go to after reducing to a nonterminal
+    gotos: BTreeMap<(usize, String), usize>,
+}
+
+// *********************************************************************
+/// Implement the methods of the LR0 structure
+impl LR0 {
+    /// Build the canonical LR(0) collection of the augmented grammar
+    pub fn new(grammar: &Grammar) -> LR0 {
+        let mut start = format!("{}'", grammar.start);
+        while grammar.nonterminals().contains(&start) {
+            start.push('\'');
+        }
+        let mut augmented = Grammar {
+            start: start.clone(),
+            productions: grammar.productions.clone(),
+        };
+        augmented.productions.insert(
+            0,
+            Production {
+                head: start,
+                body: vec![Symbol::Nonterminal(grammar.start.clone())],
+            },
+        );
+
+        let mut lr0 = LR0 {
+            grammar: augmented,
+            states: Vec::new(),
+            goto: BTreeMap::new(),
+        };
+        lr0.states.push(lr0.closure(vec![(0, 0)]));
+
+        let mut i = 0;
+        while i < lr0.states.len() {
+            // symbols after the dot, in order of first appearance
+            let mut symbols: Vec<Symbol> = Vec::new();
+            for &(p, dot) in &lr0.states[i] {
+                if let Some(symbol) = lr0.grammar.productions[p].body.get(dot) {
+                    if !symbols.contains(symbol) {
+                        symbols.push(symbol.clone());
+                    }
+                }
+            }
+
+            for symbol in symbols {
+                let kernel: Vec<Item> = lr0.states[i]
+                    .iter()
+                    .filter(|&&(p, dot)| lr0.grammar.productions[p].body.get(dot) == Some(&symbol))
+                    .map(|&(p, dot)| (p, dot + 1))
+                    .collect();
+                let target = lr0.closure(kernel);
+                let n = match lr0.states.iter().position(|s| *s == target) {
+                    Some(n) => n,
+                    None => {
+                        lr0.states.push(target);
+                        lr0.states.len() - 1
+                    }
+                };
+                lr0.goto.insert((i, symbol), n);
+            }
+            i += 1;
+        }
+
+        lr0
+    }
+
+    /// Add the items `B -> • γ` for every nonterminal after a dot
+    fn closure(&self, mut items: Vec<Item>) -> Vec<Item> {
+        let mut i = 0;
+        while i < items.len() {
+            let (p, dot) = items[i];
+            if let Some(Symbol::Nonterminal(name)) = self.grammar.productions[p].body.get(dot) {
+                for (n, q) in self.grammar.productions.iter().enumerate() {
+                    if &q.head == name && !items.contains(&(n, 0)) {
+                        items.push((n, 0));
+                    }
+                }
+            }
+            i += 1;
+        }
+        items.sort_unstable();
+        items
+    }
+
+    /// Printable form of an item `A -> α • β`
+    fn item_string(&self, (p, dot): Item) -> String {
+        let production = &self.grammar.productions[p];
+        let mut symbols: Vec<String> = production.body.iter().map(|s| s.to_string()).collect();
+        symbols.insert(dot, "•".to_string());
+        format!(
+            "{} -> {}",
+            nonterminal_name(&production.head),
+            symbols.join(" ")
+        )
+    }
+
+    /// Print the numbered productions of the augmented grammar
+    pub fn print_productions(&self) {
+        println!("Productions:");
+        for (n, p) in self.grammar.productions.iter().enumerate() {
+            println!("\t{}: {}", n, p);
+        }
+    }
+
+    /// Print every state with its items and transitions
+    pub fn print_states(&self) {
+        println!("LR(0) item sets:");
+        for (n, items) in self.states.iter().enumerate() {
+            println!("I{}:", n);
+            for item in items {
+                println!("\t{}", self.item_string(*item));
+            }
+            for ((from, symbol), to) in &self.goto {
+                if *from == n {
+                    println!("\tgoto({}) = I{}", symbol, to);
+                }
+            }
+        }
+    }
+
+    /// Write the automaton to stdout with the item sets as node labels
+    pub fn write_graphviz(&self) {
+        println!("digraph {{");
+        println!("\trankdir=LR;");
+        println!("\tnode [shape=point]; start;");
+        println!("\tnode [shape=box];");
+        for (n, items) in self.states.iter().enumerate() {
+            let label: Vec<String> = items
+                .iter()
+                .map(|item| escape(&self.item_string(*item)))
+                .collect();
+            // the state that can accept is drawn with a double border
+            let peripheries = if items.contains(&(0, 1)) { 2 } else { 1 };
+            println!(
+                "\tq{} [label=\"I{}\\n{}\\l\", peripheries={}];",
+                n,
+                n,
+                label.join("\\l"),
+                peripheries
+            );
+        }
+        println!("\tstart -> q0");
+        self.edges().write();
+
+        println!("}}");
+    }
+
+    /// Write the automaton to stdout as a Mermaid state diagram, each
+    /// state listing its items
+    pub fn write_mermaid(&self) {
+        let mut diagram = StateDiagram::new("q0");
+        for (n, items) in self.states.iter().enumerate() {
+            for item in items {
+                diagram
+                    .descriptions
+                    .push((format!("q{}", n), self.item_string(*item)));
+            }
+            if items.contains(&(0, 1)) {
+                diagram.accept.push(format!("q{}", n));
+            }
+        }
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every goto symbol
+    fn edges(&self) -> Edges {
+        let mut edges = Edges::new();
+        for ((from, symbol), to) in &self.goto {
+            edges.add(
+                &format!("q{}", from),
+                &format!("q{}", to),
+                &symbol.to_string(),
+            );
+        }
+        edges
+    }
+
+    /// SLR(1) table: reduce `A -> α` on every terminal in FOLLOW(A)
+    pub fn slr_table(&self) -> LrTable {
+        let ll1 = LL1::new(&self.grammar);
+        self.table("SLR(1)", |_, (p, _)| {
+            ll1.follow(&self.grammar.productions[p].head).clone()
+        })
+    }
+
+    /// LALR(1) table: reduce on the lookaheads propagated through the
+    /// LR(0) automaton, the same as merging LR(1) states with equal cores
+    pub fn lalr_table(&self) -> LrTable {
+        let ll1 = LL1::new(&self.grammar);
+        let mut lookaheads: Vec<BTreeMap<Item, BTreeSet<char>>> = self
+            .states
+            .iter()
+            .map(|items| items.iter().map(|item| (*item, BTreeSet::new())).collect())
+            .collect();
+        lookaheads[0].get_mut(&(0, 0)).unwrap().insert(END);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (n, items) in self.states.iter().enumerate() {
+                for &(p, dot) in items {
+                    let body = &self.grammar.productions[p].body;
+                    let current = lookaheads[n][&(p, dot)].clone();
+                    match body.get(dot) {
+                        None => {}
+                        Some(symbol) => {
+                            // spontaneous and propagated lookaheads of B -> • γ
+                            if let Symbol::Nonterminal(name) = symbol {
+                                let (mut first, nullable) = ll1.first_of(&body[dot + 1..]);
+                                if nullable {
+                                    first.extend(current.iter().cloned());
+                                }
+                                for &(q, qdot) in items {
+                                    if qdot == 0 && &self.grammar.productions[q].head == name {
+                                        let set = lookaheads[n].get_mut(&(q, 0)).unwrap();
+                                        let before = set.len();
+                                        set.extend(first.iter().cloned());
+                                        changed |= set.len() != before;
+                                    }
+                                }
+                            }
+
+                            // carried along the goto transition
+                            let target = self.goto[&(n, symbol.clone())];
+                            let set = lookaheads[target].get_mut(&(p, dot + 1)).unwrap();
+                            let before = set.len();
+                            set.extend(current);
+                            changed |= set.len() != before;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.table("LALR(1)", |n, item| lookaheads[n][&item].clone())
+    }
+
+    /// Build the tables, reducing complete items on `reduce_on(state, item)`
+    fn table(
+        &self,
+        kind: &'static str,
+        reduce_on: impl Fn(usize, Item) -> BTreeSet<char>,
+    ) -> LrTable {
+        let mut table = LrTable {
+            kind,
+            actions: BTreeMap::new(),
+            gotos: BTreeMap::new(),
+        };
+
+        for (n, items) in self.states.iter().enumerate() {
+            for &(p, dot) in items {
+                match self.grammar.productions[p].body.get(dot) {
+                    Some(Symbol::Terminal(c)) => {
+                        table.add(n, *c, Action::Shift(self.goto[&(n, Symbol::Terminal(*c))]));
+                    }
+                    Some(Symbol::Nonterminal(_)) => {}
+                    None if p == 0 => table.add(n, END, Action::Accept),
+                    None => {
+                        for c in reduce_on(n, (p, dot)) {
+                            table.add(n, c, Action::Reduce(p));
+                        }
+                    }
+                }
+            }
+        }
+
+        for ((from, symbol), to) in &self.goto {
+            if let Symbol::Nonterminal(name) = symbol {
+                table.gotos.insert((*from, name.clone()), *to);
+            }
+        }
+
+        table
+    }
+}
+
+// *********************************************************************
+/// Implement the methods of the LrTable structure
+impl LrTable {
+    /// Add an action to a cell unless it is already there
+    fn add(&mut self, state: usize, lookahead: char, action: Action) {
+        let cell = self.actions.entry((state, lookahead)).or_default();
+        if !cell.contains(&action) {
+            cell.push(action);
+        }
+    }
+
+    /// Print the ACTION and GOTO tables, one row per state
+    pub fn print(&self, lr0: &LR0) {
+        let mut terminals = lr0.grammar.terminals();
+        terminals.push(END);
+        let nonterminals: Vec<String> = lr0.grammar.nonterminals().into_iter().skip(1).collect();
+
+        let mut header: Vec<String> = terminals.iter().map(|c| c.to_string()).collect();
+        header.extend(nonterminals.iter().map(|n| nonterminal_name(n)));
+
+        let rows: Vec<Vec<String>> = (0..lr0.states.len())
+            .map(|n| {
+                let mut row: Vec<String> = terminals
+                    .iter()
+                    .map(|c| match self.actions.get(&(n, *c)) {
+                        Some(actions) => {
+                            let actions: Vec<String> =
+                                actions.iter().map(|a| a.to_string()).collect();
+                            actions.join("/")
+                        }
+                        None => String::new(),
+                    })
+                    .collect();
+                row.extend(nonterminals.iter().map(
+                    |name| match self.gotos.get(&(n, name.clone())) {
+                        Some(to) => to.to_string(),
+                        None => String::new(),
+                    },
+                ));
+                row
+            })
+            .collect();
+
+        let width = rows
+            .iter()
+            .flatten()
+            .chain(header.iter())
+            .map(|c| c.chars().count())
+            .max()
+            .unwrap_or(1);
+        let state_width = format!("{}", lr0.states.len()).len().max(5);
+
+        println!("{} table (ACTION | GOTO):", self.kind);
+        let cells: Vec<String> = header
+            .iter()
+            .map(|c| format!("{:<w$}", c, w = width))
+            .collect();
+        println!(
+            "{}",
+            format!("{:<sw$} | {}", "State", cells.join(" | "), sw = state_width).trim_end()
+        );
+        for (n, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|c| format!("{:<w$}", c, w = width))
+                .collect();
+            println!(
+                "{}",
+                format!("{:<sw$} | {}", n, cells.join(" | "), sw = state_width).trim_end()
+            );
+        }
+    }
+
+    /// Describe every cell holding more than one action
+    pub fn conflicts(&self, lr0: &LR0) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for ((state, lookahead), actions) in &self.actions {
+            for (i, first) in actions.iter().enumerate() {
+                for second in &actions[i + 1..] {
+                    let kind = match (first, second) {
+                        (Action::Reduce(_), Action::Reduce(_)) => "reduce/reduce",
+                        (Action::Shift(_), Action::Reduce(_))
+                        | (Action::Reduce(_), Action::Shift(_)) => "shift/reduce",
+                        (Action::Accept, Action::Reduce(_))
+                        | (Action::Reduce(_), Action::Accept) => "accept/reduce",
+                        (Action::Shift(_), Action::Accept) | (Action::Accept, Action::Shift(_)) => {
+                            "shift/accept"
+                        }
+                        (Action::Shift(_), Action::Shift(_)) => "shift/shift",
+                        (Action::Accept, Action::Accept) => "accept/accept",
+                    };
+                    conflicts.push(format!(
+                        "{} conflict in state {} on {}: {} vs {}",
+                        kind,
+                        state,
+                        lookahead,
+                        action_string(lr0, *first),
+                        action_string(lr0, *second)
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Run the shift-reduce parser, returning acceptance and every step
+    pub fn parse(&self, lr0: &LR0, input: &str) -> (bool, Vec<Step>) {
+        let input: Vec<char> = input.chars().collect();
+        let mut states: Vec<usize> = vec![0];
+        let mut symbols: Vec<String> = Vec::new();
+        let mut pos = 0;
+        let mut steps = Vec::new();
+
+        loop {
+            let lookahead = input.get(pos).copied().unwrap_or(END);
+            let mut stack = states[0].to_string();
+            for (symbol, state) in symbols.iter().zip(&states[1..]) {
+                stack.push_str(&format!(" {} {}", symbol, state));
+            }
+            let mut step = Step {
+                stack,
+                input: input[pos..].iter().chain(std::iter::once(&END)).collect(),
+                action: String::new(),
+            };
+
+            let state = *states.last().unwrap();
+            let action = self.actions.get(&(state, lookahead)).map(|a| a[0]);
+            match action {
+                Some(Action::Shift(to)) => {
+                    step.action = format!("shift {}", to);
+                    symbols.push(lookahead.to_string());
+                    states.push(to);
+                    pos += 1;
+                }
+                Some(Action::Reduce(p)) => {
+                    let production = &lr0.grammar.productions[p];
+                    step.action = format!("reduce {}", production);
+                    for _ in 0..production.body.len() {
+                        states.pop();
+                        symbols.pop();
+                    }
+                    let top = *states.last().unwrap();
+                    match self.gotos.get(&(top, production.head.clone())) {
+                        Some(to) => {
+                            symbols.push(nonterminal_name(&production.head));
+                            states.push(*to);
+                        }
+                        None => {
+                            step.action.push_str(", error: no goto");
+                            steps.push(step);
+                            return (false, steps);
+                        }
+                    }
+                }
+                Some(Action::Accept) => {
+                    step.action = "accept".to_string();
+                    steps.push(step);
+                    return (true, steps);
+                }
+                None => {
+                    step.action = format!("error: no action for state {} on {}", state, lookahead);
+                    steps.push(step);
+                    return (false, steps);
+                }
+            }
+            steps.push(step);
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    /// Short table form: `s3`, `r2` or `acc`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Shift(n) => write!(f, "s{}", n),
+            Action::Reduce(n) => write!(f, "r{}", n),
+            Action::Accept => write!(f, "acc"),
+        }
+    }
+}
+
+// *********************************************************************
+/// Long form of an action naming the production reduced by
+fn action_string(lr0: &LR0, action: Action) -> String {
+    match action {
+        Action::Shift(n) => format!("shift {}", n),
+        Action::Reduce(n) => format!("reduce {}", lr0.grammar.productions[n]),
+        Action::Accept => "accept".to_string(),
+    }
+}
+
+// Test Functions
+#[test]
+fn test_slr_and_lalr_tables() {
+    let g = Grammar::parse("E -> E + T | T\nT -> T * a | a").unwrap();
+    let lr0 = LR0::new(&g);
+    let slr = lr0.slr_table();
+    assert!(slr.conflicts(&lr0).is_empty());
+    assert!(slr.parse(&lr0, "a+a*a").0);
+    assert!(!slr.parse(&lr0, "a+*a").0);
+
+    // the classic grammar that is LALR(1) but not SLR(1)
+    let g = Grammar::parse("S -> L = R | R\nL -> * R | i\nR -> L").unwrap();
+    let lr0 = LR0::new(&g);
+    assert_eq!(lr0.slr_table().conflicts(&lr0).len(), 1);
+    let lalr = lr0.lalr_table();
+    assert!(lalr.conflicts(&lr0).is_empty());
+    let (accepted, steps) = lalr.parse(&lr0, "*i=i");
+    assert!(accepted);
+    assert_eq!(steps[0].action, "shift 4");
+
+    // S -> S. both accepts and reduces at the end of the input
+    let g = Grammar::parse("S -> S | a").unwrap();
+    let lr0 = LR0::new(&g);
+    let conflicts = lr0.slr_table().conflicts(&lr0);
+    assert_eq!(conflicts.len(), 1);
+    assert!(conflicts[0].starts_with("accept/reduce conflict"));
+}
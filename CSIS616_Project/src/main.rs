@@ -4,6 +4,18 @@ use std::io::BufRead;
 use std::io::Write;
 use std::process;
 
+mod ambiguity;
+mod cnf;
+mod earley;
+mod fst;
+mod grammar;
+mod ll1;
+mod lr;
+mod regular;
+
+use automata_draw::{graphviz, mermaid, output_filename, svg};
+use grammar::Grammar;
+
 // ***********************************************************************
 ///
 #[derive(Debug)]
@@ -52,6 +64,12 @@ fn main() {
     let args: Vec<String> = std::env::args().collect();
     let mut input = String::new();
 
+    // Grammar commands: main <command> <grammar file> [string]
+    if args.len() > 2 {
+        run_grammar_command(&args);
+        return;
+    }
+
     if args.len() == 1 {
         // Prompt
         println!("User input required: ");
@@ -68,81 +86,11 @@ fn main() {
         input = fs::read_to_string(filename).expect("Something went wrong reading the file");
     }
 
-    // Splits regEx into vector of chars
-    let reg_ex: Vec<char> = input.trim_end().chars().collect();
-
-    // Makes sure the RegEx will not be rejected
-    check_reg_ex_chars(&reg_ex);
-
-    // Get alphabet
-    let alphabet = get_alphabet(&reg_ex);
-
-    // First Parse of original regex
-    let mut expressions: Vec<Vec<char>> = parse_original(&reg_ex);
-
-    // Parse the expressions
-    expressions = simplify_expressions(&expressions);
-
-    // Get number of states
-    let states: Vec<usize> = get_states(&expressions);
-
-    // Start state alwasy 1
-    let start: usize = 1;
-
-    // Get trainsitions
-    let transitions: Vec<Vec<usize>> = get_transitions(&expressions);
-
-    // Get accept states
-    let accept_states: Vec<usize> = get_accept_states(&expressions);
-
-    // Get transition symbols
-    let transition_symbols: Vec<Vec<char>> = get_transition_symbols(&expressions);
-
-    // Initialize the NFA
-    let nfa: NFA = NFA {
-        alphabet: alphabet,
-        start: start,
-        accept: accept_states,
-        transitions: transitions,
-        transition_symbols: transition_symbols,
-        states: states,
-    };
-
-    // Initialize states for StateGraph
-    let mut state_graph_states: Vec<State> = Vec::new();
-    for state in &nfa.states {
-        let mut state_transitions: Vec<usize> = Vec::new();
-        let mut state_accept_state: bool = false;
-
-        // gets all states current state transitions to
-        for transition in &nfa.transitions {
-            if state == &transition[0] {
-                state_transitions.push(transition[1]);
-            } else if state == &transition[1] {
-                state_transitions.push(transition[0]);
-            }
-        }
+    // Build the NFA for the regular expression
+    let nfa = nfa_from_reg_ex(&input);
 
-        // Finds if state is an accept state
-        for num in &nfa.accept {
-            if state == num {
-                state_accept_state = true;
-                break;
-            }
-        }
-        let curr_state: State = State {
-            accept_state: state_accept_state,
-            transitions: state_transitions,
-        };
-
-        state_graph_states.push(curr_state);
-    }
-
-    // Initialize the StateGraph
-    let state_graph: StateGraph = StateGraph {
-        nfa: nfa,
-        states: state_graph_states,
-    };
+    // Get a state structure for the NFA
+    let state_graph = StateGraph::new_from_nfa(nfa);
 
     // Write graphviz
     state_graph.write_graphviz();
@@ -183,6 +131,429 @@ fn get_filename(args: std::env::Args) -> String {
     args[1].to_string()
 }
 
+// *********************************************************************
+/// Print the machine of the kind in `args[2]` from the file in `args[3]`
+/// as a Mermaid state diagram
+fn run_mermaid(args: &[String]) {
+    let filename = match args.get(3) {
+        Some(filename) => filename,
+        None => {
+            println!("Error: mermaid needs a kind and a file.");
+            process::exit(1);
+        }
+    };
+    match args[2].as_str() {
+        "regex" => {
+            let input =
+                fs::read_to_string(filename).expect("Something went wrong reading the file");
+            StateGraph::new_from_nfa(nfa_from_reg_ex(&input)).write_mermaid();
+        }
+        "fst" => fst::FST::new_from_file(filename).write_mermaid(),
+        "lr0" => lr::LR0::new(&Grammar::new_from_file(filename)).write_mermaid(),
+        other => {
+            println!("Error: '{}' should be regex, fst or lr0.", other);
+            process::exit(1);
+        }
+    }
+}
+
+// *********************************************************************
+/// Run the grammar command named by the first argument
+///
+/// `cnf file` prints the grammar in Chomsky normal form, `cyk file [string]`
+/// checks the string with the CYK algorithm and prints its parse tree,
+/// `earley file [string] [limit]` parses the string with the Earley
+/// algorithm and prints up to `limit` parse trees, `ambiguous file [length]`
+/// looks for a string of at most `length` characters with two parse trees,
+/// `ll1 file [string]`
+/// prints the LL(1) sets, table and conflicts and runs the predictive parser,
+/// `lr0 file` prints the LR(0) item sets and their Graphviz automaton and
+/// `slr file [string]` or `lalr file [string]` print the LR table and its
+/// conflicts and run the shift-reduce parser. `rg2nfa file [string]` turns
+/// a regular grammar into an NFA and `nfa2rg regexfile [right|left]` turns
+/// the NFA of a regular expression into a regular grammar. `simplify file`
+/// prints the grammar after each simplification step. `fst file [string]`
+/// applies a transducer to the string and prints its input and output
+/// projections, and `compose file file [string]` does the same for the
+/// composition of two transducers. `svg regexfile [svgfile]` draws the NFA
+/// of a regular expression to an SVG file without Graphviz,
+/// `tikz regexfile [texfile]` to a LaTeX file with a TikZ picture, and
+/// `trace regexfile string [htmlfile]` draws its run on the string step by
+/// step to an HTML page. `mermaid regex|fst|lr0 file` prints the NFA of a
+/// regular expression, a transducer or the LR(0) automaton of a grammar as
+/// a Mermaid state diagram.
+fn run_grammar_command(args: &[String]) {
+    if args[1] == "mermaid" {
+        run_mermaid(args);
+        return;
+    }
+    if args[1] == "fst" || args[1] == "compose" {
+        let mut transducer = fst::FST::new_from_file(&args[2]);
+        let mut next = 3;
+        if args[1] == "compose" {
+            let second = match args.get(3) {
+                Some(filename) => fst::FST::new_from_file(filename),
+                None => {
+                    println!("Error: compose needs two transducer files.");
+                    process::exit(1);
+                }
+            };
+            println!("Composition of {} and {}:", args[2], args[3]);
+            *transducer = transducer.compose(&second);
+            next = 4;
+        }
+        transducer.write_graphviz();
+        println!();
+
+        println!("Input projection:");
+        StateGraph::new_from_nfa(transducer.projection(fst::Side::Input)).write_graphviz();
+        println!();
+        println!("Output projection:");
+        StateGraph::new_from_nfa(transducer.projection(fst::Side::Output)).write_graphviz();
+        println!();
+
+        let str_input = get_input_string(args, next);
+        let alphabet = transducer.alphabet();
+        if str_input.chars().any(|c| !alphabet.contains(&c)) {
+            println!("Error: Character not in alphabet.");
+            process::exit(1);
+        }
+        let outputs = transducer.apply(&str_input);
+        if outputs.is_empty() {
+            println!("The transducer has no output for the string.");
+        } else {
+            println!("Outputs:");
+            for output in outputs {
+                println!("	{}", if output.is_empty() { "ε" } else { &output });
+            }
+        }
+        return;
+    }
+    if args[1] == "trace" {
+        let input = fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let str_input = get_input_string(args, 3);
+        let htmlfile = match args.get(4) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "html"),
+        };
+        let state_graph = StateGraph::new_from_nfa(nfa_from_reg_ex(&input));
+        state_graph.check_input_alphabet(&str_input);
+        state_graph.write_trace(&str_input, &htmlfile);
+        println!("Wrote {}", htmlfile);
+        return;
+    }
+    if args[1] == "svg" {
+        let input = fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let svgfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "svg"),
+        };
+        StateGraph::new_from_nfa(nfa_from_reg_ex(&input)).write_svg(&svgfile);
+        println!("Wrote {}", svgfile);
+        return;
+    }
+    if args[1] == "tikz" {
+        let input = fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let texfile = match args.get(3) {
+            Some(name) => name.clone(),
+            None => output_filename(&args[2], "tex"),
+        };
+        StateGraph::new_from_nfa(nfa_from_reg_ex(&input)).write_tikz(&texfile);
+        println!("Wrote {}", texfile);
+        return;
+    }
+    if args[1] == "nfa2rg" {
+        let input = fs::read_to_string(&args[2]).expect("Something went wrong reading the file");
+        let linearity = match args.get(3).map(|s| s.as_str()) {
+            None | Some("right") => regular::Linearity::Right,
+            Some("left") => regular::Linearity::Left,
+            Some(other) => {
+                println!("Error: '{}' should be right or left.", other);
+                process::exit(1);
+            }
+        };
+
+        let state_graph = StateGraph::new_from_nfa(nfa_from_reg_ex(&input));
+        state_graph.write_graphviz();
+        println!();
+        print!("{}", regular::nfa_to_grammar(&state_graph.nfa, linearity));
+        return;
+    }
+
+    let grammar = Grammar::new_from_file(&args[2]);
+
+    match args[1].as_str() {
+        "simplify" => {
+            println!("Grammar:");
+            print!("{}", grammar);
+            for step in grammar.simplification_steps() {
+                println!();
+                println!("{}:", step.name);
+                println!("{}", step.note);
+                print!("{}", step.grammar);
+            }
+        }
+        "cnf" => {
+            println!("Grammar:");
+            print!("{}", grammar);
+            println!();
+            if cnf::is_cnf(&grammar) {
+                println!("The grammar is already in Chomsky normal form.");
+            } else {
+                println!("Chomsky normal form:");
+                print!("{}", cnf::to_cnf(&grammar));
+            }
+        }
+        "cyk" => {
+            let cnf_grammar = cnf::to_cnf(&grammar);
+            println!("Chomsky normal form:");
+            print!("{}", cnf_grammar);
+            println!();
+
+            let str_input = get_input_string(args, 3);
+            check_grammar_alphabet(&grammar, &str_input);
+            let cyk = cnf::Cyk::new(&cnf_grammar, &str_input);
+            cyk.print_table();
+            println!();
+
+            match cyk.parse_tree() {
+                Some(tree) => {
+                    println!("The string is accepted by the grammar.");
+                    println!();
+                    tree.write_graphviz();
+                }
+                None => println!("The string is not accepted by the grammar."),
+            }
+        }
+        "earley" => {
+            let str_input = get_input_string(args, 3);
+            check_grammar_alphabet(&grammar, &str_input);
+            let limit: usize = match args.get(4) {
+                Some(n) => n.parse().expect("Limit must be a number"),
+                None => 10,
+            };
+
+            let parser = earley::Earley::new(&grammar, &str_input);
+            parser.print_chart();
+            println!();
+
+            if !parser.accepted() {
+                println!("The string is not accepted by the grammar.");
+                return;
+            }
+            println!("The string is accepted by the grammar.");
+
+            let trees = parser.parse_trees(limit);
+            println!("Parse trees found: {} (limit {})", trees.len(), limit);
+            for (n, tree) in trees.iter().enumerate() {
+                println!();
+                println!("Tree {}: {}", n + 1, tree);
+                tree.write_graphviz();
+            }
+        }
+        "ambiguous" => {
+            let max_length: usize = match args.get(3) {
+                Some(n) => n.parse().expect("Length must be a number"),
+                None => 8,
+            };
+
+            match ambiguity::find_ambiguous(&grammar, max_length) {
+                Some(found) => {
+                    println!(
+                        "The string \"{}\" has two leftmost derivations:",
+                        found.input
+                    );
+                    for (n, tree) in found.trees.iter().enumerate() {
+                        println!();
+                        println!("Derivation {}:", n + 1);
+                        println!("\t{}", ambiguity::leftmost_derivation(tree).join(" => "));
+                    }
+                    println!();
+                    found.write_graphviz();
+                }
+                None => println!(
+                    "No string of length {} or less has two parse trees.",
+                    max_length
+                ),
+            }
+        }
+        "ll1" => {
+            let ll1 = ll1::LL1::new(&grammar);
+            ll1.print_sets();
+            println!();
+            ll1.print_table();
+            println!();
+
+            let conflicts = ll1.conflicts();
+            if !conflicts.is_empty() {
+                println!("Conflicts:");
+                for conflict in &conflicts {
+                    println!("\t{}", conflict);
+                }
+                println!();
+                println!("The grammar is not LL(1).");
+                return;
+            }
+            println!("The grammar is LL(1).");
+            println!();
+
+            let str_input = get_input_string(args, 3);
+            let (accept, steps) = ll1.parse(&str_input);
+            ll1::print_steps(&steps);
+            println!();
+
+            if accept {
+                println!("The string is accepted by the grammar.");
+            } else {
+                println!("The string is not accepted by the grammar.");
+            }
+        }
+        "rg2nfa" => {
+            let (nfa, linearity) = match regular::grammar_to_nfa(&grammar) {
+                Ok(found) => found,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    process::exit(1);
+                }
+            };
+            match linearity {
+                regular::Linearity::Right => println!("The grammar is right-linear."),
+                regular::Linearity::Left => println!("The grammar is left-linear."),
+            }
+            println!();
+
+            let state_graph = StateGraph::new_from_nfa(nfa);
+            state_graph.write_graphviz();
+
+            if let Some(str_input) = args.get(3) {
+                println!();
+                if state_graph.nfa.accepts(str_input) {
+                    println!("The string is accepted by the graph.");
+                } else {
+                    println!("The string is not accepted by the graph.");
+                }
+            }
+        }
+        "lr0" => {
+            let lr0 = lr::LR0::new(&grammar);
+            lr0.print_productions();
+            println!();
+            lr0.print_states();
+            println!();
+            lr0.write_graphviz();
+        }
+        "slr" | "lalr" => {
+            let lr0 = lr::LR0::new(&grammar);
+            let table = if args[1] == "slr" {
+                lr0.slr_table()
+            } else {
+                lr0.lalr_table()
+            };
+            lr0.print_productions();
+            println!();
+            table.print(&lr0);
+            println!();
+
+            let conflicts = table.conflicts(&lr0);
+            if !conflicts.is_empty() {
+                println!("Conflicts:");
+                for conflict in &conflicts {
+                    println!("\t{}", conflict);
+                }
+                println!();
+                println!("The grammar is not {}.", table.kind);
+                return;
+            }
+            println!("The grammar is {}.", table.kind);
+            println!();
+
+            let str_input = get_input_string(args, 3);
+            let (accept, steps) = table.parse(&lr0, &str_input);
+            ll1::print_steps(&steps);
+            println!();
+
+            if accept {
+                println!("The string is accepted by the grammar.");
+            } else {
+                println!("The string is not accepted by the grammar.");
+            }
+        }
+        _ => {
+            eprintln!(
+                "Usage: project-1 [regexfile] | project-1 <simplify|cnf|cyk|earley|ambiguous|ll1|lr0|slr|lalr|rg2nfa|nfa2rg|fst|compose|svg|tikz|trace|mermaid> file [string]"
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+// *********************************************************************
+/// Checks that the input string only contains terminals of the grammar
+fn check_grammar_alphabet(grammar: &Grammar, s: &str) {
+    let terminals = grammar.terminals();
+    if s.chars().any(|letter| !terminals.contains(&letter)) {
+        println!("Error: Character not in alphabet.");
+        process::exit(1);
+    }
+}
+
+// *********************************************************************
+/// Return the argument at `index`, prompting for a string when it is missing
+fn get_input_string(args: &[String], index: usize) -> String {
+    if let Some(s) = args.get(index) {
+        return s.clone();
+    }
+    println!("Please enter a string:");
+    let stdin = io::stdin();
+    let str_input = stdin.lock().lines().next().unwrap().unwrap();
+    println!();
+    str_input
+}
+
+// *********************************************************************
+/// Build the NFA for a regular expression
+fn nfa_from_reg_ex(input: &str) -> NFA {
+    // Splits regEx into vector of chars
+    let reg_ex: Vec<char> = input.trim_end().chars().collect();
+
+    // Makes sure the RegEx will not be rejected
+    check_reg_ex_chars(&reg_ex);
+
+    // Get alphabet
+    let alphabet = get_alphabet(&reg_ex);
+
+    // First Parse of original regex
+    let mut expressions: Vec<Vec<char>> = parse_original(&reg_ex);
+
+    // Parse the expressions
+    expressions = simplify_expressions(&expressions);
+
+    // Get number of states
+    let states: Vec<usize> = get_states(&expressions);
+
+    // Start state alwasy 1
+    let start: usize = 1;
+
+    // Get trainsitions
+    let transitions: Vec<Vec<usize>> = get_transitions(&expressions);
+
+    // Get accept states
+    let accept_states: Vec<usize> = get_accept_states(&expressions);
+
+    // Get transition symbols
+    let transition_symbols: Vec<Vec<char>> = get_transition_symbols(&expressions);
+
+    // Initialize the NFA
+    NFA {
+        alphabet: alphabet,
+        start: start,
+        accept: accept_states,
+        transitions: transitions,
+        transition_symbols: transition_symbols,
+        states: states,
+    }
+}
+
 // *********************************************************************
 /// Checks input regular expression for errors
 fn check_reg_ex_chars(reg_ex: &Vec<char>) {
@@ -749,11 +1120,100 @@ fn get_accept_states(expressions: &Vec<Vec<char>>) -> Vec<usize> {
 
 // *********************************************************************
 /// Implement the methods of the NFA structure
-impl NFA {}
+impl NFA {
+    /// Does the NFA accept the string
+    ///
+    /// As in `check_string`, a transition back to the start state is taken
+    /// without reading a symbol.
+    fn accepts(&self, s: &str) -> bool {
+        let mut current = self.start_closure(vec![self.start]);
+        for letter in s.chars() {
+            current = self.step(&current, letter).0;
+        }
+        current.iter().any(|state| self.accept.contains(state))
+    }
+
+    /// States reached from `current` by reading `letter`, and the
+    /// transitions taken to reach them
+    fn step(&self, current: &[usize], letter: char) -> (Vec<usize>, Vec<(usize, usize)>) {
+        let mut next: Vec<usize> = Vec::new();
+        let mut taken = Vec::new();
+        for (i, transition) in self.transitions.iter().enumerate() {
+            if transition[1] != self.start
+                && current.contains(&transition[0])
+                && self.transition_symbols[i].contains(&letter)
+            {
+                taken.push((transition[0], transition[1]));
+                if !next.contains(&transition[1]) {
+                    next.push(transition[1]);
+                }
+            }
+        }
+
+        // the transitions back to the start state read nothing
+        for transition in &self.transitions {
+            if transition[1] == self.start && next.contains(&transition[0]) {
+                taken.push((transition[0], transition[1]));
+            }
+        }
+        (self.start_closure(next), taken)
+    }
+
+    /// Add the start state to a set of states that can move back to it
+    fn start_closure(&self, mut states: Vec<usize>) -> Vec<usize> {
+        let returns = self
+            .transitions
+            .iter()
+            .any(|t| t[1] == self.start && states.contains(&t[0]));
+        if returns && !states.contains(&self.start) {
+            states.push(self.start);
+        }
+        states
+    }
+}
 
 // *********************************************************************
 /// Implement the methods of the State Graph structure
 impl StateGraph {
+    /// Create a state graph from an NFA structure
+    fn new_from_nfa(nfa: NFA) -> StateGraph {
+        // Initialize states for StateGraph
+        let mut state_graph_states: Vec<State> = Vec::new();
+        for state in &nfa.states {
+            let mut state_transitions: Vec<usize> = Vec::new();
+            let mut state_accept_state: bool = false;
+
+            // gets all states current state transitions to
+            for transition in &nfa.transitions {
+                if state == &transition[0] {
+                    state_transitions.push(transition[1]);
+                } else if state == &transition[1] {
+                    state_transitions.push(transition[0]);
+                }
+            }
+
+            // Finds if state is an accept state
+            for num in &nfa.accept {
+                if state == num {
+                    state_accept_state = true;
+                    break;
+                }
+            }
+            let curr_state: State = State {
+                accept_state: state_accept_state,
+                transitions: state_transitions,
+            };
+
+            state_graph_states.push(curr_state);
+        }
+
+        // Initialize the StateGraph
+        StateGraph {
+            nfa: nfa,
+            states: state_graph_states,
+        }
+    }
+
     /// Write the graph to stdout
     fn write_graphviz(&self) {
         println!("digraph {{");
@@ -770,19 +1230,126 @@ impl StateGraph {
         // Start State
         println!("\tstart -> q{}", self.nfa.start);
 
-        // Transitions
+        // Transitions, merged when they join the same states
+        self.edges().write();
+
+        println!("}}");
+    }
+
+    /// Write the graph to stdout as a Mermaid state diagram
+    fn write_mermaid(&self) {
+        let mut diagram = mermaid::StateDiagram::new(&format!("q{}", self.nfa.start));
+        diagram.accept = self.nfa.accept.iter().map(|q| format!("q{}", q)).collect();
+        diagram.write(&self.edges());
+    }
+
+    /// One edge per pair of states, labelled with every symbol
+    fn edges(&self) -> graphviz::Edges {
+        let mut edges = graphviz::Edges::new();
         let mut i: usize = 0;
         for transition in &self.nfa.transitions {
             for n in 0..self.nfa.transition_symbols[i].len() {
-                println!(
-                    "\tq{} -> q{} [label=\"{}\"]",
-                    transition[0], transition[1], self.nfa.transition_symbols[i][n]
+                edges.add(
+                    &format!("q{}", transition[0]),
+                    &format!("q{}", transition[1]),
+                    &self.nfa.transition_symbols[i][n].to_string(),
                 );
             }
             i = i + 1;
         }
+        edges
+    }
 
-        println!("}}");
+    /// Draw the graph to the SVG file `filename`
+    fn write_svg(&self, filename: &str) {
+        fs::write(filename, self.diagram().to_svg()).expect("Unable to write output");
+    }
+
+    /// Draw the graph to the LaTeX file `filename`
+    fn write_tikz(&self, filename: &str) {
+        fs::write(filename, self.diagram().to_tikz()).expect("Unable to write output");
+    }
+
+    /// Draw the run on `s` to the HTML file `filename`, one frame per
+    /// character with the current states and the transitions just taken
+    /// highlighted
+    fn write_trace(&self, s: &str, filename: &str) {
+        let diagram = self.diagram();
+        let frames: Vec<String> = self
+            .trace(s)
+            .iter()
+            .map(|highlight| diagram.to_svg_with(highlight))
+            .collect();
+        let html = svg::animation_html(&format!("Run on \"{}\"", s), &frames);
+        fs::write(filename, html).expect("Unable to write output");
+    }
+
+    /// What each frame of the run on `s` picks out, the start and then
+    /// one frame per character
+    fn trace(&self, s: &str) -> Vec<svg::Highlight> {
+        let names = |states: &[usize]| -> Vec<String> {
+            states.iter().map(|q| format!("q{}", q)).collect()
+        };
+        let mut frames = Vec::new();
+        let mut current = self.nfa.start_closure(vec![self.nfa.start]);
+        let mut highlight = svg::Highlight {
+            states: names(&current),
+            edges: Vec::new(),
+            caption: vec!["Start".to_string()],
+        };
+        let chars: Vec<char> = s.chars().collect();
+        for n in 0..=chars.len() {
+            let read: String = chars[..n].iter().collect();
+            let left: String = chars[n..].iter().collect();
+            highlight
+                .caption
+                .push(format!("States: {{{}}}", highlight.states.join(", ")));
+            highlight.caption.push(format!("Read: {}", read));
+            highlight.caption.push(format!("Left: {}", left));
+            if n == chars.len() {
+                highlight
+                    .caption
+                    .push(if current.iter().any(|q| self.nfa.accept.contains(q)) {
+                        "The string is accepted by the graph.".to_string()
+                    } else {
+                        "The string is not accepted by the graph.".to_string()
+                    });
+            }
+            frames.push(std::mem::take(&mut highlight));
+
+            if let Some(&letter) = chars.get(n) {
+                let (next, taken) = self.nfa.step(&current, letter);
+                highlight = svg::Highlight {
+                    states: names(&next),
+                    edges: taken
+                        .iter()
+                        .map(|(from, to)| (format!("q{}", from), format!("q{}", to)))
+                        .collect(),
+                    caption: vec![format!("Read {}", letter)],
+                };
+                current = next;
+            }
+        }
+        frames
+    }
+
+    /// The graph for drawing without Graphviz
+    fn diagram(&self) -> svg::Diagram {
+        let mut diagram = svg::Diagram::new();
+        diagram.start(&format!("q{}", self.nfa.start));
+        for state in &self.nfa.states {
+            diagram.state(&format!("q{}", state), self.nfa.accept.contains(state));
+        }
+        for (i, transition) in self.nfa.transitions.iter().enumerate() {
+            for symbol in &self.nfa.transition_symbols[i] {
+                diagram.edge(
+                    &format!("q{}", transition[0]),
+                    &format!("q{}", transition[1]),
+                    &symbol.to_string(),
+                );
+            }
+        }
+        diagram
     }
 
     // checks that the input string only contains symbols from the alphabet
@@ -861,3 +1428,29 @@ impl StateGraph {
         }
     }
 }
+
+// Test Functions
+#[test]
+fn test_trace_frames() {
+    let graph = StateGraph::new_from_nfa(nfa_from_reg_ex("a|ab"));
+    let frames = graph.trace("ab");
+    assert_eq!(frames.len(), 3);
+
+    let names = |v: &[&str]| -> Vec<String> { v.iter().map(|q| q.to_string()).collect() };
+    let edges = |v: &[(&str, &str)]| -> Vec<(String, String)> {
+        v.iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    };
+    // both branches read the a, only the second goes on to read the b
+    assert_eq!(frames[0].states, names(&["q1"]));
+    assert!(frames[0].edges.is_empty());
+    assert_eq!(frames[1].states, names(&["q2", "q3"]));
+    assert_eq!(frames[1].edges, edges(&[("q1", "q2"), ("q1", "q3")]));
+    assert_eq!(frames[2].states, names(&["q4"]));
+    assert_eq!(frames[2].edges, edges(&[("q3", "q4")]));
+    assert_eq!(
+        frames[2].caption.last().unwrap(),
+        "The string is accepted by the graph."
+    );
+}
@@ -0,0 +1,307 @@
+//! Conversion between regular grammars and the NFA
+//!
+//! A right-linear grammar only has productions `A -> w B` and `A -> w`, a
+//! left-linear grammar only `A -> B w` and `A -> w`, where `w` is a
+//! string of terminals. States of the NFA follow the rest of the program:
+//! numbered from 1 with 1 as the start state, and a transition back to
+//! the start state is taken without reading a symbol.
+
+use crate::grammar::{Grammar, Production, Symbol};
+use crate::NFA;
+
+// *********************************************************************
+/// # Which side of a regular grammar's bodies the nonterminal is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linearity {
+    /// `A -> w B`
+    Right,
+
+    /// `A -> B w`
+    Left,
+}
+
+// *********************************************************************
+/// Check that a grammar is right-linear or left-linear
+///
+/// A grammar with no nonterminal on any right side is both and is
+/// reported as right-linear.
+pub fn check_regular(grammar: &Grammar) -> Result<Linearity, String> {
+    let not_right = grammar.productions.iter().find(|p| !is_right_linear(p));
+    let not_left = grammar.productions.iter().find(|p| !is_left_linear(p));
+
+    match (not_right, not_left) {
+        (None, _) => Ok(Linearity::Right),
+        (_, None) => Ok(Linearity::Left),
+        (Some(r), Some(l)) if r == l => Err(format!(
+            "The grammar is not regular: {} is neither right-linear nor left-linear",
+            r
+        )),
+        (Some(r), Some(l)) => Err(format!(
+            "The grammar is not regular: {} is not right-linear and {} is not left-linear",
+            r, l
+        )),
+    }
+}
+
+// *********************************************************************
+/// Build an NFA accepting the language of a regular grammar
+///
+/// Returns the NFA and whether the grammar was right- or left-linear.
+pub fn grammar_to_nfa(grammar: &Grammar) -> Result<(NFA, Linearity), String> {
+    let linearity = check_regular(grammar)?;
+
+    // keep the start symbol off the right sides so no transition goes back
+    // to state 1, then leave only A -> w B / A -> B w, A -> w and S -> ε
+    let mut g = grammar.clone();
+    if g.start_on_right() {
+        let new_start = g.fresh_nonterminal('S');
+        g.productions.insert(
+            0,
+            Production {
+                head: new_start.clone(),
+                body: vec![Symbol::Nonterminal(g.start.clone())],
+            },
+        );
+        g.start = new_start;
+    }
+    let g = g.simplify();
+
+    let nonterminals = g.nonterminals();
+    let mut edges: Vec<(usize, usize, char)> = Vec::new();
+    let mut accept: Vec<usize> = Vec::new();
+
+    match linearity {
+        Linearity::Right => {
+            // nonterminal k is state k + 1, the start symbol is state 1
+            let state = |name: &str| nonterminals.iter().position(|n| n == name).unwrap() + 1;
+            let last = nonterminals.len() + 1;
+            let mut next = last + 1;
+
+            for p in &g.productions {
+                let (terminals, target) = match p.body.split_last() {
+                    Some((Symbol::Nonterminal(name), rest)) => (rest, state(name)),
+                    _ => (p.body.as_slice(), last),
+                };
+                if terminals.is_empty() {
+                    accept.push(state(&p.head));
+                    continue;
+                }
+                if target == last && !accept.contains(&last) {
+                    accept.push(last);
+                }
+                add_path(&mut edges, &mut next, state(&p.head), terminals, target);
+            }
+        }
+        Linearity::Left => {
+            // state 1 is before any input, nonterminal k is state k + 2
+            let state = |name: &str| nonterminals.iter().position(|n| n == name).unwrap() + 2;
+            let mut next = nonterminals.len() + 2;
+            accept.push(state(&g.start));
+
+            for p in &g.productions {
+                let (source, terminals) = match p.body.split_first() {
+                    Some((Symbol::Nonterminal(name), rest)) => (state(name), rest),
+                    _ => (1, p.body.as_slice()),
+                };
+                if terminals.is_empty() {
+                    accept.push(1);
+                    continue;
+                }
+                add_path(&mut edges, &mut next, source, terminals, state(&p.head));
+            }
+        }
+    }
+
+    // one transition per pair of states carrying all of its symbols
+    let mut transitions: Vec<Vec<usize>> = Vec::new();
+    let mut transition_symbols: Vec<Vec<char>> = Vec::new();
+    for (from, to, c) in edges {
+        match transitions.iter().position(|t| t[0] == from && t[1] == to) {
+            Some(i) => {
+                if !transition_symbols[i].contains(&c) {
+                    transition_symbols[i].push(c);
+                }
+            }
+            None => {
+                transitions.push(vec![from, to]);
+                transition_symbols.push(vec![c]);
+            }
+        }
+    }
+
+    let n_states = transitions
+        .iter()
+        .flatten()
+        .chain(accept.iter())
+        .max()
+        .copied()
+        .unwrap_or(1);
+    accept.sort_unstable();
+    accept.dedup();
+
+    let nfa = NFA {
+        alphabet: grammar.terminals(),
+        start: 1,
+        accept,
+        transitions,
+        transition_symbols,
+        states: (1..=n_states).collect(),
+    };
+    Ok((nfa, linearity))
+}
+
+// *********************************************************************
+/// Build a regular grammar for the language of an NFA
+///
+/// State `qn` becomes nonterminal `Qn`. In the left-linear grammar a new
+/// start symbol `S` derives the accept states.
+pub fn nfa_to_grammar(nfa: &NFA, linearity: Linearity) -> Grammar {
+    let name = |state: usize| format!("Q{}", state);
+    let mut productions: Vec<Production> = Vec::new();
+
+    for (i, transition) in nfa.transitions.iter().enumerate() {
+        let (from, to) = (name(transition[0]), name(transition[1]));
+        // a move back to the start state reads nothing
+        let symbols: Vec<Option<char>> = if transition[1] == nfa.start {
+            vec![None]
+        } else {
+            nfa.transition_symbols[i].iter().map(|c| Some(*c)).collect()
+        };
+
+        for symbol in symbols {
+            let production = match (linearity, symbol) {
+                (Linearity::Right, Some(c)) => Production {
+                    head: from.clone(),
+                    body: vec![Symbol::Terminal(c), Symbol::Nonterminal(to.clone())],
+                },
+                (Linearity::Right, None) => Production {
+                    head: from.clone(),
+                    body: vec![Symbol::Nonterminal(to.clone())],
+                },
+                (Linearity::Left, Some(c)) => Production {
+                    head: to.clone(),
+                    body: vec![Symbol::Nonterminal(from.clone()), Symbol::Terminal(c)],
+                },
+                (Linearity::Left, None) => Production {
+                    head: to.clone(),
+                    body: vec![Symbol::Nonterminal(from.clone())],
+                },
+            };
+            crate::grammar::push_unique(&mut productions, production);
+        }
+    }
+
+    let start = match linearity {
+        Linearity::Right => {
+            for state in &nfa.accept {
+                productions.push(Production {
+                    head: name(*state),
+                    body: vec![],
+                });
+            }
+            name(nfa.start)
+        }
+        Linearity::Left => {
+            productions.push(Production {
+                head: name(nfa.start),
+                body: vec![],
+            });
+            for (n, state) in nfa.accept.iter().enumerate() {
+                productions.insert(
+                    n,
+                    Production {
+                        head: "S".to_string(),
+                        body: vec![Symbol::Nonterminal(name(*state))],
+                    },
+                );
+            }
+            "S".to_string()
+        }
+    };
+
+    // the start symbol leads the printed grammar
+    productions.sort_by_key(|p| p.head != start);
+    Grammar { start, productions }
+}
+
+// *********************************************************************
+/// Is the production `A -> w` or `A -> w B`
+fn is_right_linear(p: &Production) -> bool {
+    match p.body.split_last() {
+        Some((_, rest)) => rest.iter().all(|s| matches!(s, Symbol::Terminal(_))),
+        None => true,
+    }
+}
+
+// *********************************************************************
+/// Is the production `A -> w` or `A -> B w`
+fn is_left_linear(p: &Production) -> bool {
+    match p.body.split_first() {
+        Some((_, rest)) => rest.iter().all(|s| matches!(s, Symbol::Terminal(_))),
+        None => true,
+    }
+}
+
+// *********************************************************************
+/// Add transitions reading `terminals` from `from` to `to`, creating
+/// intermediate states numbered from `next`
+fn add_path(
+    edges: &mut Vec<(usize, usize, char)>,
+    next: &mut usize,
+    from: usize,
+    terminals: &[Symbol],
+    to: usize,
+) {
+    let mut current = from;
+    for (i, symbol) in terminals.iter().enumerate() {
+        if let Symbol::Terminal(c) = symbol {
+            let target = if i + 1 == terminals.len() {
+                to
+            } else {
+                *next += 1;
+                *next - 1
+            };
+            edges.push((current, target, *c));
+            current = target;
+        }
+    }
+}
+
+// Test Functions
+#[test]
+fn test_grammar_nfa_round_trip() {
+    let right = Grammar::parse("S -> a S | b A | ε\nA -> b A | c").unwrap();
+    let left = Grammar::parse("S -> S a | A | ε\nA -> A b | b").unwrap();
+    assert_eq!(check_regular(&right), Ok(Linearity::Right));
+    assert_eq!(check_regular(&left), Ok(Linearity::Left));
+    assert!(check_regular(&Grammar::parse("S -> a S b | ε").unwrap()).is_err());
+
+    // a* (b+ c)?  and  b* a*
+    let (nfa, linearity) = grammar_to_nfa(&right).unwrap();
+    assert_eq!(linearity, Linearity::Right);
+    for (s, accept) in [
+        ("", true),
+        ("aa", true),
+        ("abbc", true),
+        ("abb", false),
+        ("c", false),
+    ] {
+        assert_eq!(nfa.accepts(s), accept, "{}", s);
+    }
+    let (nfa, linearity) = grammar_to_nfa(&left).unwrap();
+    assert_eq!(linearity, Linearity::Left);
+    for (s, accept) in [("", true), ("bba", true), ("a", true), ("ab", false)] {
+        assert_eq!(nfa.accepts(s), accept, "{}", s);
+    }
+
+    // back to grammars of both kinds and to an NFA again
+    let nfa = crate::nfa_from_reg_ex("(aa|qq|5)*");
+    for linearity in [Linearity::Right, Linearity::Left] {
+        let g = nfa_to_grammar(&nfa, linearity);
+        assert_eq!(check_regular(&g), Ok(linearity));
+        let (again, _) = grammar_to_nfa(&g).unwrap();
+        for s in ["", "aa", "qq5aa", "a", "aaq", "5q"] {
+            assert_eq!(again.accepts(s), nfa.accepts(s), "{}", s);
+        }
+    }
+}
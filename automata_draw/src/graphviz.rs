@@ -0,0 +1,153 @@
+//! Graphviz output shared by the machines
+//!
+//! Edges are collected before they are written so that parallel edges
+//! become one edge carrying every label, and so that they come out in the
+//! same order on every run however they were added.
+
+use std::collections::BTreeMap;
+
+// *********************************************************************
+/// # Piece of a node name, numbers compare by value so q2 sorts before q10
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Part {
+    Text(String),
+    Number(usize),
+}
+
+/// Names of the two nodes of an edge and its labels
+pub type Edge = (String, String, Vec<String>);
+
+// *********************************************************************
+/// # Edges of a digraph, merged and sorted by node names
+#[derive(Debug, Default)]
+pub struct Edges {
+    /// Every edge, keyed by the sort order of its nodes
+    edges: BTreeMap<(Vec<Part>, Vec<Part>), Edge>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Edges structure
+impl Edges {
+    /// Create an empty set of edges
+    pub fn new() -> Edges {
+        Edges::default()
+    }
+
+    /// Add an edge labelled `label`, which is escaped when it is written
+    ///
+    /// An edge already joining the same nodes takes the label instead,
+    /// unless it already has it. An empty label adds nothing to an edge.
+    pub fn add(&mut self, from: &str, to: &str, label: &str) {
+        let (_, _, labels) = self
+            .edges
+            .entry((sort_key(from), sort_key(to)))
+            .or_insert_with(|| (from.to_string(), to.to_string(), Vec::new()));
+        if !label.is_empty() && !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
+
+    /// Every edge in the order they are written
+    pub fn iter(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.values()
+    }
+
+    /// Write every edge to stdout, labels separated by commas
+    pub fn write(&self) {
+        self.write_with(", ", None, "");
+    }
+
+    /// Write every edge to stdout, labels separated by `separator`
+    ///
+    /// An edge with more than `most` labels shows one fewer and how many
+    /// more there are. `attributes` are added to every edge.
+    pub fn write_with(&self, separator: &str, most: Option<usize>, attributes: &str) {
+        for (from, to, labels) in self.edges.values() {
+            let mut shown: Vec<String> = labels.iter().map(|l| escape(l)).collect();
+            if let Some(most) = most {
+                if shown.len() > most {
+                    let more = shown.len() + 1 - most;
+                    shown.truncate(most - 1);
+                    shown.push(format!("({} more)", more));
+                }
+            }
+
+            let mut attrs = Vec::new();
+            if !shown.is_empty() {
+                attrs.push(format!("label=\"{}\"", shown.join(separator)));
+            }
+            if !attributes.is_empty() {
+                attrs.push(attributes.to_string());
+            }
+            if attrs.is_empty() {
+                println!("\t{} -> {};", from, to);
+            } else {
+                println!("\t{} -> {} [{}];", from, to, attrs.join(", "));
+            }
+        }
+    }
+}
+
+// *********************************************************************
+/// Escape a label for a quoted DOT string
+///
+/// Backslashes and quotes get a backslash, a newline becomes `\n`, and `&`
+/// and every non-ASCII character such as ε are written as HTML entities,
+/// which Graphviz draws whatever the character set of the output.
+pub fn escape(label: &str) -> String {
+    let mut escaped = String::new();
+    for c in label.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '&' => escaped.push_str("&amp;"),
+            c if c.is_ascii() => escaped.push(c),
+            c => escaped.push_str(&format!("&#{};", c as u32)),
+        }
+    }
+    escaped
+}
+
+// *********************************************************************
+/// Split a node name into text and numbers for sorting
+fn sort_key(name: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut chars = name.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let digit = c.is_ascii_digit();
+        let mut run = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() != digit {
+                break;
+            }
+            run.push(c);
+            chars.next();
+        }
+        match run.parse() {
+            Ok(n) if digit => parts.push(Part::Number(n)),
+            _ => parts.push(Part::Text(run)),
+        }
+    }
+    parts
+}
+
+// Test Functions
+#[test]
+fn test_edges_merge_and_sort() {
+    let mut edges = Edges::new();
+    edges.add("q10", "q2", "b");
+    edges.add("q2", "q1", "\"x\"");
+    edges.add("q10", "q2", "a");
+    edges.add("q10", "q2", "b");
+    edges.add("q2", "q1", "ε\\");
+    let keys: Vec<&Edge> = edges.edges.values().collect();
+    assert_eq!(keys[0].0, "q2");
+    assert_eq!(keys[1].2, vec!["b", "a"]);
+    assert_eq!(
+        escape("\"x\", ε\\ & é"),
+        "\\\"x\\\", &#949;\\\\ &amp; &#233;"
+    );
+    assert!(sort_key("q9") < sort_key("q10"));
+    assert!(sort_key("f2_1") < sort_key("q1"));
+}
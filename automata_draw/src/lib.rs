@@ -0,0 +1,19 @@
+//! Drawing automata, shared by the CSIS616 programs
+//!
+//! Each program turns its machines into the edges and states these
+//! modules take, and gets the same Graphviz, SVG, TikZ, Mermaid and
+//! terminal output as the others.
+
+pub mod graphviz;
+pub mod mermaid;
+pub mod svg;
+pub mod terminal;
+
+// *********************************************************************
+/// Name of the file drawn from `filename`, its extension replaced
+pub fn output_filename(filename: &str, extension: &str) -> String {
+    std::path::Path::new(filename)
+        .with_extension(extension)
+        .to_string_lossy()
+        .to_string()
+}
@@ -0,0 +1,132 @@
+//! Mermaid output shared by the machines
+//!
+//! Markdown that renders Mermaid takes a `stateDiagram-v2` block, so a
+//! machine printed this way can be pasted straight into a README. The
+//! start state gets an arrow from `[*]`, each accept state one to `[*]`,
+//! and the edges are the same merged and sorted ones as the Graphviz
+//! output.
+
+use crate::graphviz::Edges;
+
+// *********************************************************************
+/// # A state diagram to be written
+#[derive(Debug, Default)]
+pub struct StateDiagram {
+    /// Name of the start state
+    pub start: String,
+
+    /// Names of the accept states
+    pub accept: Vec<String>,
+
+    /// Names of the states shaded grey
+    pub shaded: Vec<String>,
+
+    /// States shown with a label other than their name
+    pub labels: Vec<(String, String)>,
+
+    /// Lines written inside states, under their name
+    pub descriptions: Vec<(String, String)>,
+}
+
+// *********************************************************************
+/// Implement the methods of the StateDiagram structure
+impl StateDiagram {
+    /// Create a diagram starting in `start`
+    pub fn new(start: &str) -> StateDiagram {
+        StateDiagram {
+            start: start.to_string(),
+            ..StateDiagram::default()
+        }
+    }
+
+    /// Write the diagram with `edges` to stdout
+    pub fn write(&self, edges: &Edges) {
+        print!("{}", self.render(edges));
+    }
+
+    /// The diagram with `edges`
+    ///
+    /// Labels of one edge are separated by commas, or put on lines of
+    /// their own when they have commas themselves.
+    pub fn render(&self, edges: &Edges) -> String {
+        let mut lines = vec!["stateDiagram-v2".to_string(), "direction LR".to_string()];
+        for (state, label) in &self.labels {
+            lines.push(format!("state \"{}\" as {}", escape(label), state));
+        }
+        for (state, text) in &self.descriptions {
+            lines.push(format!("{} : {}", state, escape(text)));
+        }
+        lines.push(format!("[*] --> {}", self.start));
+        for (from, to, labels) in edges.iter() {
+            let shown: Vec<String> = labels.iter().map(|l| escape(l)).collect();
+            let separator = if labels.iter().any(|l| l.contains(',')) {
+                "<br>"
+            } else {
+                ", "
+            };
+            if shown.is_empty() {
+                lines.push(format!("{} --> {}", from, to));
+            } else {
+                lines.push(format!("{} --> {} : {}", from, to, shown.join(separator)));
+            }
+        }
+        for state in &self.accept {
+            lines.push(format!("{} --> [*]", state));
+        }
+        if !self.shaded.is_empty() {
+            lines.push("classDef shaded fill:lightgrey".to_string());
+            lines.push(format!("class {} shaded", self.shaded.join(",")));
+        }
+
+        let mut text = lines.remove(0) + "\n";
+        for line in lines {
+            text.push_str(&format!("    {}\n", line));
+        }
+        text
+    }
+}
+
+// *********************************************************************
+/// Escape text for a Mermaid label
+///
+/// Characters Mermaid reads as syntax, such as `:`, `;`, `#`, `<` and
+/// `>`, and every non-ASCII character such as ε are written as `#N;`
+/// entity codes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || " ,.-_+=()'/*|!?^$@&~".contains(c) {
+            escaped.push(c);
+        } else {
+            escaped.push_str(&format!("#{};", c as u32));
+        }
+    }
+    escaped
+}
+
+// Test Functions
+#[test]
+fn test_state_diagram() {
+    let mut edges = Edges::new();
+    edges.add("q1", "q2", "b");
+    edges.add("q1", "q2", "a");
+    edges.add("q2", "q2", "x, e -> x");
+    edges.add("q2", "q2", "ε:y");
+    let mut diagram = StateDiagram::new("q1");
+    diagram.accept.push("q2".to_string());
+    diagram.labels.push(("q1".to_string(), "q1/0".to_string()));
+    diagram.shaded.push("q2".to_string());
+    assert_eq!(
+        diagram.render(&edges),
+        "stateDiagram-v2
+    direction LR
+    state \"q1/0\" as q1
+    [*] --> q1
+    q1 --> q2 : b, a
+    q2 --> q2 : x, e -#62; x<br>#949;#58;y
+    q2 --> [*]
+    classDef shaded fill:lightgrey
+    class q2 shaded
+"
+    );
+}
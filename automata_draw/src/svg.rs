@@ -0,0 +1,745 @@
+//! SVG drawings of state graphs without Graphviz
+//!
+//! States are placed in layers from left to right by their distance from
+//! the start state, like `rankdir=LR`, and each layer is ordered by the
+//! average position of its neighbours to keep crossings down. An edge to
+//! the next layer is a straight line, a state's edge to itself is a loop
+//! above it, and every other edge is a curve, forward edges bending above
+//! and back edges below the states they pass.
+//!
+//! A run is drawn as one frame per step on the same layout, with the
+//! current states and the edges just taken highlighted, and the frames
+//! are bundled into an HTML page that steps through them.
+//!
+//! The same layout gives a TikZ picture for LaTeX, drawn with the
+//! `automata` library.
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Radius of a state
+const RADIUS: f64 = 18.0;
+
+/// Distance between the centres of two layers
+const LAYER_GAP: f64 = 110.0;
+
+/// Distance between the centres of two states in a layer
+const ROW_GAP: f64 = 70.0;
+
+/// Space left around the drawing
+const MARGIN: f64 = 20.0;
+
+/// Rough width of a character of a label
+const CHAR_WIDTH: f64 = 7.0;
+
+/// Distance between the lines of a label
+const LINE_HEIGHT: f64 = 14.0;
+
+/// Colour of a highlighted state
+const MARKED_FILL: &str = "#ffd966";
+
+/// Colour of a highlighted edge
+const MARKED_STROKE: &str = "#c00000";
+
+/// Pixels of the layout to a centimetre of a TikZ picture
+const TIKZ_SCALE: f64 = 40.0;
+
+/// Number of times the layers are reordered in each direction
+const SWEEPS: usize = 4;
+
+// *********************************************************************
+/// # A state graph to be drawn
+#[derive(Debug, Default)]
+pub struct Diagram {
+    /// Name of each state, in the order they were added
+    names: Vec<String>,
+
+    /// Is each state an accept state
+    accept: Vec<bool>,
+
+    /// Index of the start state, if there is one
+    start: Option<usize>,
+
+    /// Labels of the edge between each pair of states
+    edges: BTreeMap<(usize, usize), Vec<String>>,
+}
+
+// *********************************************************************
+/// # What one frame of a run picks out
+#[derive(Debug, Default)]
+pub struct Highlight {
+    /// Names of the current states
+    pub states: Vec<String>,
+
+    /// Names of the states of each edge just taken
+    pub edges: Vec<(String, String)>,
+
+    /// Lines written under the drawing, such as the input read so far
+    pub caption: Vec<String>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Diagram structure
+impl Diagram {
+    /// Create an empty diagram
+    pub fn new() -> Diagram {
+        Diagram::default()
+    }
+
+    /// Index of the state called `name`, adding it when it is new
+    fn index(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_string());
+                self.accept.push(false);
+                self.names.len() - 1
+            }
+        }
+    }
+
+    /// Add a state, drawn as a double circle when `accept` is set
+    pub fn state(&mut self, name: &str, accept: bool) {
+        let i = self.index(name);
+        self.accept[i] |= accept;
+    }
+
+    /// Make `name` the start state, drawn with an arrow coming in
+    pub fn start(&mut self, name: &str) {
+        self.start = Some(self.index(name));
+    }
+
+    /// Add an edge, merged with any other edge joining the same states
+    pub fn edge(&mut self, from: &str, to: &str, label: &str) {
+        let key = (self.index(from), self.index(to));
+        let labels = self.edges.entry(key).or_default();
+        if !label.is_empty() && !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
+
+    /// Layer of each state, its distance from the start state
+    ///
+    /// States the start state cannot reach are laid out from the first of
+    /// them in the same way, starting again at the first layer.
+    fn layers(&self) -> Vec<usize> {
+        let mut layer: Vec<Option<usize>> = vec![None; self.names.len()];
+        let roots = self.start.into_iter().chain(0..self.names.len());
+        for root in roots {
+            if layer[root].is_some() {
+                continue;
+            }
+            layer[root] = Some(0);
+            let mut queue = VecDeque::from(vec![root]);
+            while let Some(state) = queue.pop_front() {
+                for &(from, to) in self.edges.keys() {
+                    if from == state && layer[to].is_none() {
+                        layer[to] = Some(layer[state].unwrap() + 1);
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        layer.into_iter().map(|l| l.unwrap()).collect()
+    }
+
+    /// States of each layer from top to bottom
+    ///
+    /// Each sweep sorts a layer by the average position of the states it
+    /// shares an edge with in the layer before it, first from left to
+    /// right and then back again.
+    fn order(&self, layer: &[usize]) -> Vec<Vec<usize>> {
+        let count = layer.iter().max().map_or(0, |l| l + 1);
+        let mut rows: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for (state, l) in layer.iter().enumerate() {
+            rows[*l].push(state);
+        }
+
+        let mut position = vec![0.0; layer.len()];
+        let number = |rows: &Vec<Vec<usize>>, position: &mut Vec<f64>| {
+            for row in rows {
+                for (i, state) in row.iter().enumerate() {
+                    position[*state] = i as f64;
+                }
+            }
+        };
+        number(&rows, &mut position);
+
+        let down: Vec<(usize, usize)> = (1..count).map(|l| (l, l - 1)).collect();
+        let up: Vec<(usize, usize)> = (0..count.saturating_sub(1))
+            .rev()
+            .map(|l| (l, l + 1))
+            .collect();
+        for _ in 0..SWEEPS {
+            for &(l, other) in down.iter().chain(up.iter()) {
+                let mut keyed: Vec<(f64, usize)> = rows[l]
+                    .iter()
+                    .map(|state| {
+                        let near: Vec<f64> = self
+                            .edges
+                            .keys()
+                            .filter_map(|&(from, to)| {
+                                let n = if from == *state { to } else { from };
+                                if (from == *state || to == *state) && layer[n] == other {
+                                    Some(position[n])
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect();
+                        if near.is_empty() {
+                            (position[*state], *state)
+                        } else {
+                            (near.iter().sum::<f64>() / near.len() as f64, *state)
+                        }
+                    })
+                    .collect();
+                keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                rows[l] = keyed.into_iter().map(|(_, state)| state).collect();
+                number(&rows, &mut position);
+            }
+        }
+        rows
+    }
+
+    /// Lines of the label of the edge with `labels`
+    ///
+    /// Labels with commas of their own go one per line.
+    fn lines(labels: &[String]) -> Vec<String> {
+        if labels.iter().any(|l| l.contains(',')) {
+            labels.to_vec()
+        } else {
+            vec![labels.join(", ")]
+        }
+    }
+
+    /// Centre of each state, layers centred on the same line
+    ///
+    /// Layers are pushed further apart when a label between them would
+    /// not fit.
+    fn positions(&self) -> (Vec<usize>, Vec<(f64, f64)>) {
+        let layer = self.layers();
+        let rows = self.order(&layer);
+        let mut gap = vec![LAYER_GAP; rows.len()];
+        for (&(from, to), labels) in &self.edges {
+            if layer[to] == layer[from] + 1 {
+                let widest = Diagram::lines(labels)
+                    .iter()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let needed = widest as f64 * CHAR_WIDTH + 2.0 * RADIUS + 20.0;
+                gap[layer[from]] = gap[layer[from]].max(needed);
+            }
+        }
+
+        let mut centre = vec![(0.0, 0.0); self.names.len()];
+        let mut x = 0.0;
+        for (l, row) in rows.iter().enumerate() {
+            let middle = (row.len() as f64 - 1.0) / 2.0;
+            for (i, state) in row.iter().enumerate() {
+                centre[*state] = (x, (i as f64 - middle) * ROW_GAP);
+            }
+            x += gap[l];
+        }
+        (layer, centre)
+    }
+
+    /// The drawing as a standalone SVG document
+    pub fn to_svg(&self) -> String {
+        self.to_svg_with(&Highlight::default())
+    }
+
+    /// The drawing as a standalone SVG document with `highlight` picked
+    /// out in colour and its caption underneath
+    pub fn to_svg_with(&self, highlight: &Highlight) -> String {
+        let (layer, centre) = self.positions();
+        let mut canvas = Canvas::new();
+
+        // start arrow
+        if let Some(start) = self.start {
+            let (x, y) = centre[start];
+            canvas.line((x - RADIUS - 30.0, y), (x - RADIUS, y), false);
+        }
+
+        for (&(from, to), labels) in &self.edges {
+            let lines = Diagram::lines(labels);
+            let (p, q) = (centre[from], centre[to]);
+            let marked = highlight
+                .edges
+                .iter()
+                .any(|(a, b)| *a == self.names[from] && *b == self.names[to]);
+            if from == to {
+                canvas.self_loop(p, &lines, marked);
+            } else if layer[to] == layer[from] + 1 {
+                let (a, b) = (towards(p, q), towards(q, p));
+                canvas.line(a, b, marked);
+                canvas.label((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0 - 6.0, &lines, true);
+            } else {
+                canvas.curve(p, q, &lines, marked);
+            }
+        }
+
+        // states last so that they cover the ends of the edges
+        for (state, name) in self.names.iter().enumerate() {
+            let marked = highlight.states.contains(name);
+            canvas.state(centre[state], name, self.accept[state], marked);
+        }
+        canvas.caption(&highlight.caption);
+        canvas.finish()
+    }
+}
+
+// *********************************************************************
+/// Implement the TikZ output of the Diagram structure
+impl Diagram {
+    /// The drawing as a standalone LaTeX document with a TikZ picture,
+    /// states placed as in the SVG
+    ///
+    /// Edges to the next layer are straight, loops go above their state
+    /// and every other edge bends left, like the curves of the SVG.
+    pub fn to_tikz(&self) -> String {
+        let (layer, centre) = self.positions();
+        let mut tex = String::new();
+        tex.push_str("\\documentclass[tikz, border=5pt]{standalone}\n");
+        tex.push_str("\\usepackage[T1]{fontenc}\n");
+        tex.push_str("\\usetikzlibrary{automata, arrows.meta}\n");
+        tex.push_str("\\begin{document}\n");
+        tex.push_str("\\begin{tikzpicture}[>=Stealth, auto, initial text=,\n");
+        tex.push_str("    every state/.append style={minimum size=0.9cm}]\n");
+        for (state, name) in self.names.iter().enumerate() {
+            let mut style = vec!["state"];
+            if self.start == Some(state) {
+                style.push("initial");
+            }
+            if self.accept[state] {
+                style.push("accepting");
+            }
+            let (x, y) = centre[state];
+            tex.push_str(&format!(
+                "  \\node[{}] (s{}) at ({:.2}, {:.2}) {{{}}};\n",
+                style.join(", "),
+                state,
+                x / TIKZ_SCALE,
+                (0.0 - y) / TIKZ_SCALE,
+                latex_name(name)
+            ));
+        }
+
+        if !self.edges.is_empty() {
+            tex.push_str("  \\path[->]\n");
+            for (&(from, to), labels) in &self.edges {
+                let lines: Vec<String> = Diagram::lines(labels).iter().map(|l| latex(l)).collect();
+                let (bend, target) = if from == to {
+                    ("[loop above] ", "()".to_string())
+                } else if layer[to] == layer[from] + 1 {
+                    ("", format!("(s{})", to))
+                } else {
+                    ("[bend left] ", format!("(s{})", to))
+                };
+                tex.push_str(&format!(
+                    "    (s{}) edge {}node[align=center] {{{}}} {}\n",
+                    from,
+                    bend,
+                    lines.join(" \\\\ "),
+                    target
+                ));
+            }
+            tex.push_str("  ;\n");
+        }
+        tex.push_str("\\end{tikzpicture}\n");
+        tex.push_str("\\end{document}\n");
+        tex
+    }
+}
+
+// *********************************************************************
+/// # SVG elements and the box around them
+struct Canvas {
+    /// Elements drawn so far
+    body: Vec<String>,
+
+    /// Smallest and largest x and y of anything drawn
+    bounds: (f64, f64, f64, f64),
+}
+
+// *********************************************************************
+/// Implement the methods of the Canvas structure
+impl Canvas {
+    /// Create an empty canvas
+    fn new() -> Canvas {
+        Canvas {
+            body: Vec::new(),
+            bounds: (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        }
+    }
+
+    /// Grow the box to take in the point `(x, y)`
+    fn include(&mut self, x: f64, y: f64) {
+        let (x0, y0, x1, y1) = self.bounds;
+        self.bounds = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+    }
+
+    /// Draw a state with its name, a double circle for an accept state
+    /// and filled in when it is `marked`
+    fn state(&mut self, (x, y): (f64, f64), name: &str, accept: bool, marked: bool) {
+        let fill = if marked { MARKED_FILL } else { "white" };
+        self.body.push(format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"{}\" stroke=\"black\"/>",
+            x, y, RADIUS, fill
+        ));
+        if accept {
+            self.body.push(format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"none\" stroke=\"black\"/>",
+                x,
+                y,
+                RADIUS - 4.0
+            ));
+        }
+        self.include(x - RADIUS, y - RADIUS);
+        self.include(x + RADIUS, y + RADIUS);
+        self.text(x, y + 4.0, name);
+    }
+
+    /// Draw an arrow from `a` to `b`
+    fn line(&mut self, a: (f64, f64), b: (f64, f64), marked: bool) {
+        self.body.push(format!(
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" {}/>",
+            a.0,
+            a.1,
+            b.0,
+            b.1,
+            stroke(marked)
+        ));
+        self.include(a.0, a.1);
+        self.include(b.0, b.1);
+    }
+
+    /// Draw an arrow from the state at `p` back to itself
+    fn self_loop(&mut self, (x, y): (f64, f64), lines: &[String], marked: bool) {
+        let a = (x - RADIUS * 0.5, y - RADIUS * 0.87);
+        let b = (x + RADIUS * 0.5, y - RADIUS * 0.87);
+        self.body.push(format!(
+            "<path d=\"M {:.1} {:.1} C {:.1} {:.1}, {:.1} {:.1}, {:.1} {:.1}\" fill=\"none\" {}/>",
+            a.0,
+            a.1,
+            a.0 - 20.0,
+            a.1 - 40.0,
+            b.0 + 20.0,
+            b.1 - 40.0,
+            b.0,
+            b.1,
+            stroke(marked)
+        ));
+        self.include(x, a.1 - 32.0);
+        self.label(x, a.1 - 36.0, lines, true);
+    }
+
+    /// Draw an arrow curving from the state at `p` to the one at `q`
+    ///
+    /// The curve bends to the left of the way it goes, so two edges
+    /// between the same states bend apart.
+    fn curve(&mut self, p: (f64, f64), q: (f64, f64), lines: &[String], marked: bool) {
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let (nx, ny) = (dy / length, -dx / length);
+        let bend = 0.2 * length + 25.0;
+        let control = ((p.0 + q.0) / 2.0 + nx * bend, (p.1 + q.1) / 2.0 + ny * bend);
+        let (a, b) = (towards(p, control), towards(q, control));
+        self.body.push(format!(
+            "<path d=\"M {:.1} {:.1} Q {:.1} {:.1}, {:.1} {:.1}\" fill=\"none\" {}/>",
+            a.0,
+            a.1,
+            control.0,
+            control.1,
+            b.0,
+            b.1,
+            stroke(marked)
+        ));
+
+        // halfway along the curve, moved a little further out
+        let middle = (
+            (a.0 + 2.0 * control.0 + b.0) / 4.0 + nx * 10.0,
+            (a.1 + 2.0 * control.1 + b.1) / 4.0 + ny * 10.0,
+        );
+        self.include(middle.0, middle.1);
+        if ny > 0.0 {
+            self.label(middle.0, middle.1 + 12.0, lines, false);
+        } else {
+            self.label(middle.0, middle.1, lines, true);
+        }
+    }
+
+    /// Write the lines of a label centred on `x`, going up from the
+    /// baseline `y` or down from it
+    fn label(&mut self, x: f64, y: f64, lines: &[String], up: bool) {
+        for (i, line) in lines.iter().enumerate() {
+            let row = if up {
+                i as f64 + 1.0 - lines.len() as f64
+            } else {
+                i as f64
+            };
+            self.text(x, y + row * LINE_HEIGHT, line);
+        }
+    }
+
+    /// Write `text` centred on `x` with its baseline at `y`
+    fn text(&mut self, x: f64, y: f64, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let half = text.chars().count() as f64 * CHAR_WIDTH / 2.0;
+        self.include(x - half, y - 12.0);
+        self.include(x + half, y + 4.0);
+        self.body.push(format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            x,
+            y,
+            escape(text)
+        ));
+    }
+
+    /// Write `lines` left aligned under everything drawn so far
+    fn caption(&mut self, lines: &[String]) {
+        let (x0, _, _, y1) = self.bounds;
+        for (i, line) in lines.iter().enumerate() {
+            let y = y1 + 24.0 + i as f64 * LINE_HEIGHT;
+            let width = line.chars().count() as f64 * CHAR_WIDTH;
+            self.include(x0 + width, y + 4.0);
+            self.body.push(format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                x0,
+                y,
+                escape(line)
+            ));
+        }
+    }
+
+    /// The document holding everything drawn
+    fn finish(self) -> String {
+        let (mut x0, mut y0, mut x1, mut y1) = self.bounds;
+        if self.body.is_empty() {
+            x0 = 0.0;
+            y0 = 0.0;
+            x1 = 0.0;
+            y1 = 0.0;
+        }
+        let (x, y) = (x0 - MARGIN, y0 - MARGIN);
+        let (width, height) = (x1 - x0 + 2.0 * MARGIN, y1 - y0 + 2.0 * MARGIN);
+
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"{:.1} {:.1} {:.1} {:.1}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+            width, height, x, y, width, height
+        ));
+        svg.push_str("<defs>");
+        for (id, colour) in &[("arrow", "black"), ("marked", MARKED_STROKE)] {
+            svg.push_str(&format!(
+                "<marker id=\"{}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\"/></marker>",
+                id, colour
+            ));
+        }
+        svg.push_str("</defs>\n");
+        for element in self.body {
+            svg.push_str(&element);
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// *********************************************************************
+/// Stroke and arrowhead attributes of an edge
+fn stroke(marked: bool) -> String {
+    if marked {
+        format!(
+            "stroke=\"{}\" stroke-width=\"2\" marker-end=\"url(#marked)\"",
+            MARKED_STROKE
+        )
+    } else {
+        "stroke=\"black\" marker-end=\"url(#arrow)\"".to_string()
+    }
+}
+
+// *********************************************************************
+/// Point on the edge of the state at `p` facing `q`
+fn towards(p: (f64, f64), q: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    (p.0 + dx / length * RADIUS, p.1 + dy / length * RADIUS)
+}
+
+// *********************************************************************
+/// Escape text for an SVG document
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// *********************************************************************
+/// Escape text for LaTeX, with the symbols the machines use written in
+/// math mode
+fn latex(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '^' => escaped.push_str("\\^{}"),
+            '~' => escaped.push_str("\\~{}"),
+            'ε' => escaped.push_str("$\\varepsilon$"),
+            'ω' => escaped.push_str("$\\omega$"),
+            '•' => escaped.push_str("$\\bullet$"),
+            '×' => escaped.push_str("$\\times$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// *********************************************************************
+/// A state name for LaTeX, `q12` written as $q_{12}$
+fn latex_name(name: &str) -> String {
+    let digits = name.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let letters = &name[..name.len() - digits.len()];
+    if !letters.is_empty() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        format!("${}_{{{}}}$", letters, digits)
+    } else {
+        latex(name)
+    }
+}
+
+// *********************************************************************
+/// A self-contained HTML page showing one SVG frame at a time, with
+/// buttons (or the arrow keys) to step back and forward
+///
+/// Each frame is an image of its own so that the arrowheads the frames
+/// all define do not clash.
+pub fn animation_html(title: &str, frames: &[String]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str("<style>body { font-family: sans-serif; } .frame { display: none; } .frame.shown { display: block; }</style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape(title)));
+    html.push_str("<p><button id=\"previous\">Previous</button> <span id=\"step\"></span> <button id=\"next\">Next</button></p>\n");
+    for frame in frames {
+        html.push_str(&format!(
+            "<img class=\"frame\" src=\"data:image/svg+xml,{}\">\n",
+            data_uri(frame)
+        ));
+    }
+    html.push_str(
+        "<script>
+var frames = document.getElementsByClassName(\"frame\");
+var current = 0;
+function show(n) {
+    if (n < 0 || n >= frames.length) {
+        return;
+    }
+    frames[current].classList.remove(\"shown\");
+    current = n;
+    frames[current].classList.add(\"shown\");
+    document.getElementById(\"step\").textContent = \"Step \" + current + \" of \" + (frames.length - 1);
+}
+document.getElementById(\"previous\").onclick = function () { show(current - 1); };
+document.getElementById(\"next\").onclick = function () { show(current + 1); };
+document.onkeydown = function (e) {
+    if (e.key === \"ArrowLeft\") {
+        show(current - 1);
+    } else if (e.key === \"ArrowRight\") {
+        show(current + 1);
+    }
+};
+show(0);
+</script>
+",
+    );
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// *********************************************************************
+/// Percent-encode a document for a data URI
+fn data_uri(document: &str) -> String {
+    let mut encoded = String::new();
+    for byte in document.bytes() {
+        if byte.is_ascii_alphanumeric() || b" <>=/:.,;-_()!*~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// Test Functions
+#[test]
+fn test_diagram_layout() {
+    let mut diagram = Diagram::new();
+    diagram.start("q1");
+    diagram.state("q3", true);
+    diagram.edge("q1", "q2", "a");
+    diagram.edge("q1", "q3", "b");
+    diagram.edge("q2", "q4", "a");
+    diagram.edge("q3", "q4", "b");
+    diagram.edge("q4", "q1", "a");
+    diagram.edge("q4", "q4", "<b>");
+    diagram.edge("q4", "q4", "<b>");
+    assert_eq!(diagram.names, vec!["q1", "q3", "q2", "q4"]);
+    assert_eq!(diagram.layers(), vec![0, 1, 1, 2]);
+    assert_eq!(diagram.order(&diagram.layers())[1].len(), 2);
+
+    let svg = diagram.to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    // four states, one of them accepting
+    assert_eq!(svg.matches("<circle").count(), 5);
+    // start arrow and four edges to the next layer
+    assert_eq!(svg.matches("<line").count(), 5);
+    // the back edge and the loop, plus the two arrowheads
+    assert_eq!(svg.matches("<path").count(), 4);
+    assert_eq!(svg.matches("&lt;b&gt;").count(), 1);
+
+    // labels with commas of their own go one per line
+    diagram.edge("q4", "q1", "c");
+    assert!(diagram.to_svg().contains(">a, c</text>"));
+    diagram.edge("q4", "q1", "d, e");
+    let svg = diagram.to_svg();
+    assert!(svg.contains(">a</text>") && svg.contains(">d, e</text>"));
+    assert!(!svg.contains("url(#marked)\"/>"));
+
+    // one frame of a run
+    let frame = diagram.to_svg_with(&Highlight {
+        states: vec!["q2".to_string()],
+        edges: vec![("q1".to_string(), "q2".to_string())],
+        caption: vec!["Read: a".to_string()],
+    });
+    assert_eq!(frame.matches("fill=\"#ffd966\"").count(), 1);
+    assert_eq!(frame.matches("url(#marked)\"/>").count(), 1);
+    assert!(frame.contains(">Read: a</text>"));
+    let html = animation_html("a & b", &[svg, frame]);
+    assert_eq!(html.matches("<img class=\"frame\"").count(), 2);
+    assert_eq!(
+        data_uri("<a href=\"#x\">ε</a>"),
+        "<a href=%22%23x%22>%CE%B5</a>"
+    );
+    assert!(html.contains("<title>a &amp; b</title>"));
+
+    // the same layout in TikZ
+    let tex = diagram.to_tikz();
+    assert!(tex.starts_with("\\documentclass[tikz, border=5pt]{standalone}"));
+    assert!(tex.contains("\\node[state, initial] (s0) at (0.00, 0.00) {$q_{1}$};"));
+    assert!(tex.contains("\\node[state, accepting] (s1)"));
+    assert!(tex.contains("(s3) edge [loop above] node[align=center] {<b>} ()"));
+    assert!(tex.contains("(s3) edge [bend left] node[align=center] {a \\\\ c \\\\ d, e} (s0)"));
+    assert_eq!(tex.matches(" edge ").count(), 6);
+    assert!(tex.ends_with("\\end{tikzpicture}\n\\end{document}\n"));
+    assert_eq!(latex("ε_1 & $"), "$\\varepsilon$\\_1 \\& \\$");
+    assert_eq!(latex_name("f1_1"), "f1\\_1");
+}
@@ -0,0 +1,278 @@
+//! Terminal views of a machine
+//!
+//! For when no image can be opened, such as over SSH: the transition table
+//! in a box of Unicode line-drawing characters, and the state graph drawn
+//! as a tree grown from the start state. In both the start state is
+//! marked with → and each accept state with *.
+
+use crate::graphviz::Edges;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// *********************************************************************
+/// # A transition table to be printed
+#[derive(Debug, Default)]
+pub struct Table {
+    /// Heading of each column after the states
+    columns: Vec<String>,
+
+    /// Each row, the marked state first and then one entry per column
+    rows: Vec<Vec<String>>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Table structure
+impl Table {
+    /// Create a table with no rows and the column headings `columns`
+    pub fn new(columns: Vec<String>) -> Table {
+        Table {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add the row of `state`, an entry for each column
+    ///
+    /// An entry with newlines takes a line of the table for each of its
+    /// lines.
+    pub fn row(&mut self, state: &str, start: bool, accept: bool, entries: Vec<String>) {
+        let mut row = vec![marked(state, start, accept)];
+        row.extend(entries);
+        self.rows.push(row);
+    }
+
+    /// The table, one line of text for each line of the box
+    pub fn render(&self) -> String {
+        let heading: Vec<String> = std::iter::once(String::new())
+            .chain(self.columns.iter().cloned())
+            .collect();
+        let mut widths = vec![0; heading.len()];
+        for row in std::iter::once(&heading).chain(&self.rows) {
+            for (i, entry) in row.iter().enumerate() {
+                for line in entry.lines() {
+                    widths[i] = widths[i].max(line.chars().count());
+                }
+            }
+        }
+
+        let rule = |left: &str, middle: &str, right: &str| {
+            let pieces: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            format!("{}{}{}\n", left, pieces.join(middle), right)
+        };
+        let mut text = rule("┌", "┬", "┐");
+        text.push_str(&boxed_row(&heading, &widths));
+        text.push_str(&rule("├", "┼", "┤"));
+        for row in &self.rows {
+            text.push_str(&boxed_row(row, &widths));
+        }
+        text.push_str(&rule("└", "┴", "┘"));
+        text
+    }
+}
+
+// *********************************************************************
+/// Lines of one row of a table, every entry padded to its column width
+fn boxed_row(row: &[String], widths: &[usize]) -> String {
+    let height = row.iter().map(|e| e.lines().count()).max().unwrap_or(0);
+    let mut text = String::new();
+    for n in 0..height.max(1) {
+        for (entry, width) in row.iter().zip(widths) {
+            let line = entry.lines().nth(n).unwrap_or("");
+            text.push_str(&format!("│ {:<w$} ", line, w = width));
+        }
+        text.push_str("│\n");
+    }
+    text
+}
+
+// *********************************************************************
+/// Name of a state with → before it when it is the start state and *
+/// when it is an accept state
+fn marked(state: &str, start: bool, accept: bool) -> String {
+    format!(
+        "{}{}{}",
+        if start { "→" } else { " " },
+        if accept { "*" } else { " " },
+        state
+    )
+}
+
+// *********************************************************************
+/// The graph with the states `states` drawn as a tree from the start state
+///
+/// Each state is drawn with its loops once, under the first state that
+/// reaches it going breadth first, so its depth is its distance from the
+/// start state. Any other edge into it ends in its name followed by …,
+/// and states the start state cannot reach begin trees of their own.
+pub fn draw(states: &[String], start: &str, accept: &[String], edges: &Edges) -> String {
+    let mut names: Vec<&str> = vec![start];
+    let ends = edges.iter().flat_map(|(from, to, _)| vec![from, to]);
+    for name in states.iter().chain(ends) {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+
+    // the first edge reaching each state, breadth first from each root
+    let mut parent: HashMap<&str, &str> = HashMap::new();
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut roots = Vec::new();
+    for &root in &names {
+        if !reached.insert(root) {
+            continue;
+        }
+        roots.push(root);
+        let mut queue = VecDeque::from(vec![root]);
+        while let Some(state) = queue.pop_front() {
+            for (_, to, _) in edges.iter().filter(|(from, _, _)| from == state) {
+                if reached.insert(to) {
+                    parent.insert(to, state);
+                    queue.push_back(to);
+                }
+            }
+        }
+    }
+
+    let tree = Tree {
+        start,
+        accept,
+        edges,
+        parent,
+    };
+    let mut text = String::new();
+    for root in roots {
+        text.push_str(&format!("{}{}\n", tree.name(root), tree.loops(root)));
+        tree.branches(root, "", &mut text);
+    }
+    text
+}
+
+// *********************************************************************
+/// # A graph being drawn as a tree
+struct Tree<'a> {
+    /// Name of the start state
+    start: &'a str,
+
+    /// Names of the accept states
+    accept: &'a [String],
+
+    /// Every edge of the graph
+    edges: &'a Edges,
+
+    /// State each state is drawn under
+    parent: HashMap<&'a str, &'a str>,
+}
+
+// *********************************************************************
+/// Implement the methods of the Tree structure
+impl<'a> Tree<'a> {
+    /// Name of a state with its marks and no padding
+    fn name(&self, state: &str) -> String {
+        let accept = self.accept.iter().any(|a| a == state);
+        format!(
+            "{}{}{}",
+            if state == self.start { "→" } else { "" },
+            if accept { "*" } else { "" },
+            state
+        )
+    }
+
+    /// Labels of the loop on a state, if it has one
+    fn loops(&self, state: &str) -> String {
+        match self.edges.iter().find(|(f, t, _)| f == state && t == state) {
+            Some((_, _, labels)) => format!("  ↺ {}", join(labels)),
+            None => String::new(),
+        }
+    }
+
+    /// Append the edges out of `state` to `text`, under lines starting
+    /// with `prefix`, and the states drawn under it after each of them
+    fn branches(&self, state: &str, prefix: &str, text: &mut String) {
+        let out: Vec<_> = self
+            .edges
+            .iter()
+            .filter(|(f, t, _)| f == state && t != state)
+            .collect();
+        for (i, (_, to, labels)) in out.iter().enumerate() {
+            let last = i + 1 == out.len();
+            let arrow = if labels.is_empty() {
+                "──▶".to_string()
+            } else {
+                format!("─ {} ─▶", join(labels))
+            };
+            let under = self.parent.get(to.as_str()) == Some(&state);
+            text.push_str(&format!(
+                "{}{}{} {}{}\n",
+                prefix,
+                if last { "└" } else { "├" },
+                arrow,
+                self.name(to),
+                if under {
+                    self.loops(to)
+                } else {
+                    " …".to_string()
+                }
+            ));
+            if under {
+                let inner = format!("{}{}", prefix, if last { "   " } else { "│  " });
+                self.branches(to, &inner, text);
+            }
+        }
+    }
+}
+
+// *********************************************************************
+/// Labels of one edge, separated by commas or by bars when they have
+/// commas themselves
+fn join(labels: &[String]) -> String {
+    if labels.iter().any(|l| l.contains(',')) {
+        labels.join(" | ")
+    } else {
+        labels.join(", ")
+    }
+}
+
+// Test Functions
+#[test]
+fn test_terminal_views() {
+    let mut table = Table::new(vec!["a".to_string(), "b".to_string()]);
+    table.row("q1", true, false, vec!["q2".to_string(), "q1".to_string()]);
+    table.row(
+        "q2",
+        false,
+        true,
+        vec!["q2".to_string(), "q1\nq10".to_string()],
+    );
+    assert_eq!(
+        table.render(),
+        "┌──────┬────┬─────┐
+│      │ a  │ b   │
+├──────┼────┼─────┤
+│ → q1 │ q2 │ q1  │
+│  *q2 │ q2 │ q1  │
+│      │    │ q10 │
+└──────┴────┴─────┘
+"
+    );
+
+    let mut edges = Edges::new();
+    edges.add("q1", "q2", "a");
+    edges.add("q1", "q3", "b");
+    edges.add("q2", "q2", "a");
+    edges.add("q2", "q3", "b");
+    edges.add("q3", "q1", "a, e -> x");
+    edges.add("q3", "q1", "b, e -> y");
+    edges.add("q4", "q1", "");
+    let states: Vec<String> = (1..=5).map(|n| format!("q{}", n)).collect();
+    assert_eq!(
+        draw(&states, "q1", &["q3".to_string()], &edges),
+        "→q1
+├─ a ─▶ q2  ↺ a
+│  └─ b ─▶ *q3 …
+└─ b ─▶ *q3
+   └─ a, e -> x | b, e -> y ─▶ →q1 …
+q4
+└──▶ →q1 …
+q5
+"
+    );
+}